@@ -0,0 +1,72 @@
+//! Provides terminal animation of seating simulations.
+
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+use crate::seating_map::{SeatingMap, VisibilityGraph};
+
+/// Clears the terminal and moves the cursor to the top left.
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+const RESET: &str = "\x1b[0m";
+const FLOOR_COLOR: &str = "\x1b[90m";
+const EMPTY_COLOR: &str = "\x1b[32m";
+const FILLED_COLOR: &str = "\x1b[31m";
+/// Added to a tile's colour when it changed since the previous generation.
+const CHANGED_STYLE: &str = "\x1b[1;7m";
+
+/// Renders a seating map with ANSI colours, highlighting tiles that differ from
+/// `prev`.
+fn render_frame(map: &SeatingMap, prev: Option<&SeatingMap>) -> String {
+    let mut frame = String::new();
+    for y in 0..map.height() {
+        for x in 0..map.width() {
+            let ch = map.tile_char(x, y);
+            frame.push_str(match ch {
+                'L' => EMPTY_COLOR,
+                '#' => FILLED_COLOR,
+                _ => FLOOR_COLOR,
+            });
+            if prev.is_some_and(|p| p.tile_char(x, y) != ch) {
+                frame.push_str(CHANGED_STYLE);
+            }
+            frame.push(ch);
+            frame.push_str(RESET);
+        }
+        frame.push('\n');
+    }
+    frame
+}
+
+/// Draws the generations of a seating simulation to the terminal.
+pub struct Animator<'a> {
+    /// Starting seating map, used for the positions of floors.
+    map: &'a SeatingMap,
+    /// The previously drawn generation, if any.
+    prev: Option<SeatingMap>,
+    /// Time to wait after drawing each generation.
+    delay: Duration,
+}
+
+impl<'a> Animator<'a> {
+    /// Creates an animator for simulations starting from `map`.
+    pub fn new(map: &'a SeatingMap, delay: Duration) -> Self {
+        Self {
+            map,
+            prev: None,
+            delay,
+        }
+    }
+
+    /// Draws a generation, highlighting seats that changed since the last one drawn.
+    pub fn draw(&mut self, generation: usize, graph: &VisibilityGraph, state: &[bool]) {
+        let current = self.map.with_seat_state(graph, state);
+        let frame = render_frame(&current, self.prev.as_ref());
+        let mut out = io::stdout();
+        // Failing to draw a frame shouldn't stop the simulation.
+        let _ = write!(out, "{}Generation {}\n{}", CLEAR_SCREEN, generation, frame);
+        let _ = out.flush();
+        thread::sleep(self.delay);
+        self.prev = Some(current);
+    }
+}
@@ -1,15 +1,115 @@
+mod animate;
 mod seating_map;
+mod stats;
 
 use std::env;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::time::Duration;
 use utils::read_lines;
 
-use seating_map::SeatingMap;
+use animate::Animator;
+use seating_map::{Neighborhood, SeatingMap, SeatingRules, SimulationResult};
+use stats::{write_csv, write_heatmap, StatsRecorder};
+
+/// Default limit on how many generations to simulate.
+const DEFAULT_MAX_GENERATIONS: usize = 10_000;
+
+/// Default time between animation frames, in milliseconds.
+const DEFAULT_FRAME_DELAY_MS: u64 = 200;
+
+/// Command line options.
+struct Options {
+    input_path: String,
+    /// Custom seating rules to simulate, if any rule flags were given.
+    custom_rules: Option<SeatingRules>,
+    /// Limit on how many generations to simulate.
+    max_generations: usize,
+    /// Whether to draw every generation to the terminal.
+    animate: bool,
+    /// Time between animation frames.
+    frame_delay: Duration,
+    /// Directory to write per-seat statistics to, if any.
+    stats_dir: Option<PathBuf>,
+}
 
 /// Prints usage statement for the executable.
 fn usage(args: Vec<String>) {
-    println!("Usage: {} input_file scope", args[0]);
+    println!(
+        "Usage: {} input_file [--tolerance N] [--neighborhood moore|von-neumann|diagonal] [--max-distance N|none] [--max-generations N] [--animate] [--delay MS] [--stats DIR]",
+        args[0]
+    );
+}
+
+/// Parses command line arguments. Custom seating rules start from the sightline rules.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let input_path = match args.get(1) {
+        Some(p) => p.clone(),
+        None => return Err("Missing input file".into()),
+    };
+    let mut opts = Options {
+        input_path,
+        custom_rules: None,
+        max_generations: DEFAULT_MAX_GENERATIONS,
+        animate: false,
+        frame_delay: Duration::from_millis(DEFAULT_FRAME_DELAY_MS),
+        stats_dir: None,
+    };
+    let mut iter = args.iter().skip(2);
+    while let Some(flag) = iter.next() {
+        if flag == "--animate" {
+            opts.animate = true;
+            continue;
+        }
+        let value = match iter.next() {
+            Some(v) => v,
+            None => return Err(format!("Missing value for {}", flag)),
+        };
+        if flag == "--max-generations" {
+            opts.max_generations = value
+                .parse()
+                .map_err(|_| format!("Invalid max generations: {}", value))?;
+            continue;
+        }
+        if flag == "--delay" {
+            let ms = value
+                .parse()
+                .map_err(|_| format!("Invalid delay: {}", value))?;
+            opts.frame_delay = Duration::from_millis(ms);
+            continue;
+        }
+        if flag == "--stats" {
+            opts.stats_dir = Some(PathBuf::from(value));
+            continue;
+        }
+        let rules = opts
+            .custom_rules
+            .get_or_insert_with(SeatingRules::sightline);
+        match flag.as_str() {
+            "--tolerance" => {
+                rules.tolerance = value
+                    .parse()
+                    .map_err(|_| format!("Invalid tolerance: {}", value))?
+            }
+            "--neighborhood" => {
+                rules.neighborhood = Neighborhood::from_name(value)
+                    .ok_or(format!("Invalid neighborhood: {}", value))?
+            }
+            "--max-distance" => {
+                rules.max_distance = if value == "none" {
+                    None
+                } else {
+                    match value.parse() {
+                        Ok(0) | Err(_) => return Err(format!("Invalid max distance: {}", value)),
+                        Ok(d) => Some(d),
+                    }
+                }
+            }
+            _ => return Err(format!("Unexpected argument: {}", flag)),
+        }
+    }
+    Ok(opts)
 }
 
 /// Reads a seating map from a file.
@@ -33,7 +133,7 @@ where
         };
         chars.push(line.chars().collect());
     }
-    if chars.len() == 0 {
+    if chars.is_empty() {
         return Ok(SeatingMap::new(0, 0));
     }
     let mut map = SeatingMap::new(chars[0].len(), chars.len());
@@ -47,14 +147,72 @@ where
     Ok(map)
 }
 
+/// Shuffles seat occupants around until they settle, animating if requested.
+/// Per-seat statistics are written to `{label}.csv` and `{label}.ppm` in the stats
+/// directory, if one is given.
+fn run_rules(
+    map: &SeatingMap,
+    rules: &SeatingRules,
+    label: &str,
+    opts: &Options,
+) -> Result<SimulationResult, String> {
+    let mut animator = if opts.animate {
+        Some(Animator::new(map, opts.frame_delay))
+    } else {
+        None
+    };
+    let mut recorder = opts.stats_dir.as_ref().map(|_| StatsRecorder::new());
+    if animator.is_none() && recorder.is_none() {
+        return Ok(map.get_stable(rules, opts.max_generations));
+    }
+    let result = map.simulate(rules, opts.max_generations, |generation, graph, state| {
+        if let Some(animator) = animator.as_mut() {
+            animator.draw(generation, graph, state);
+        }
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record(generation, graph, state);
+        }
+    });
+
+    if let (Some(dir), Some(recorder)) = (&opts.stats_dir, recorder) {
+        let csv_path = dir.join(format!("{}.csv", label));
+        write_csv(&csv_path, recorder.stats())
+            .map_err(|e| format!("Failed to write {}: {}", csv_path.display(), e))?;
+        let heatmap_path = dir.join(format!("{}.ppm", label));
+        write_heatmap(&heatmap_path, map.width(), map.height(), recorder.stats())
+            .map_err(|e| format!("Failed to write {}: {}", heatmap_path.display(), e))?;
+    }
+    Ok(result)
+}
+
+/// Runs a simulation and reports how it ended and how many seats were left filled.
+fn report_rules(map: &SeatingMap, rules: &SeatingRules, label: &str, desc: &str, opts: &Options) {
+    let result = match run_rules(map, rules, label, opts) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    println!("Seating {}", result);
+    println!(
+        "Final number of filled seats {}: {}",
+        desc,
+        result.map.count_filled_seats()
+    );
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        usage(args);
-        process::exit(1);
-    }
-    let input_path = String::from(&args[1]);
-    let seating_map = match read_seating_map(&input_path) {
+    let opts = match parse_args(&args) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}", e);
+            usage(args);
+            process::exit(1);
+        }
+    };
+    let seating_map = match read_seating_map(&opts.input_path) {
         Ok(map) => map,
         Err(e) => {
             eprintln!("Failed to read seating map: {}", e);
@@ -62,13 +220,36 @@ fn main() {
         }
     };
 
+    if let Some(dir) = &opts.stats_dir {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("Failed to create stats directory: {}", e);
+            process::exit(1);
+        }
+    }
+
     println!("\n==== Part 1 ====");
-    let stable_adj = seating_map.get_stable_adj();
-    let filled_adj = stable_adj.count_filled_seats();
-    println!("Final number of filled seats checking adjacently: {}", filled_adj);
+    let adjacent = SeatingRules::adjacent();
+    report_rules(
+        &seating_map,
+        &adjacent,
+        "adjacent",
+        "checking adjacently",
+        &opts,
+    );
 
     println!("\n==== Part 2 ====");
-    let stable_sight = seating_map.get_stable_sightline();
-    let filled_sight = stable_sight.count_filled_seats();
-    println!("Final number of filled seats checking sightlines: {}", filled_sight);
+    let sightline = SeatingRules::sightline();
+    report_rules(
+        &seating_map,
+        &sightline,
+        "sightline",
+        "checking sightlines",
+        &opts,
+    );
+
+    if let Some(rules) = opts.custom_rules {
+        println!("\n==== Custom rules ====");
+        println!("{:?}", rules);
+        report_rules(&seating_map, &rules, "custom", "with custom rules", &opts);
+    }
 }
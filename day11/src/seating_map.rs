@@ -1,5 +1,7 @@
-use std::cmp::min;
-use std::usize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
 /// A single tile for a seating map.
 #[derive(Clone, Copy, PartialEq)]
@@ -9,6 +11,123 @@ enum SeatingTile {
     FilledSeat,
 }
 
+/// Directions checked for neighbouring seats.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Neighborhood {
+    /// All eight surrounding directions.
+    Moore,
+    /// Only up, down, left, and right.
+    VonNeumann,
+    /// Only the four diagonals.
+    Diagonal,
+}
+
+impl Neighborhood {
+    /// Parses a neighbourhood from its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "moore" => Some(Self::Moore),
+            "von-neumann" => Some(Self::VonNeumann),
+            "diagonal" => Some(Self::Diagonal),
+            _ => None,
+        }
+    }
+
+    /// Gets the (dx, dy) step for each direction in the neighbourhood.
+    fn directions(&self) -> &'static [(isize, isize)] {
+        match self {
+            Self::Moore => &[
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+            Self::VonNeumann => &[(0, -1), (-1, 0), (1, 0), (0, 1)],
+            Self::Diagonal => &[(-1, -1), (1, -1), (-1, 1), (1, 1)],
+        }
+    }
+}
+
+/// Rules for how seat occupants move around.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SeatingRules {
+    /// Number of visible occupied seats at which an occupant leaves their seat.
+    pub tolerance: usize,
+    /// Directions an occupant looks in.
+    pub neighborhood: Neighborhood,
+    /// How many tiles an occupant can see along each direction, or `None` to look
+    /// until the edge of the map.
+    pub max_distance: Option<usize>,
+}
+
+impl SeatingRules {
+    /// Rules where occupants only consider directly adjacent seats.
+    pub fn adjacent() -> Self {
+        Self {
+            tolerance: 4,
+            neighborhood: Neighborhood::Moore,
+            max_distance: Some(1),
+        }
+    }
+
+    /// Rules where occupants consider the first seat in each direction.
+    pub fn sightline() -> Self {
+        Self {
+            tolerance: 5,
+            neighborhood: Neighborhood::Moore,
+            max_distance: None,
+        }
+    }
+}
+
+/// How a seating simulation ended.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Settlement {
+    /// Seat positions stopped changing.
+    Stable,
+    /// Seat positions started repeating every `period` generations.
+    Cycle { period: usize },
+    /// The generation limit was hit before the seats stabilized or repeated.
+    LimitReached,
+}
+
+/// The result of shuffling seat occupants around until they settle.
+pub struct SimulationResult {
+    /// The seating map at the last simulated generation.
+    pub map: SeatingMap,
+    /// How the simulation ended.
+    pub settlement: Settlement,
+    /// Number of generations simulated to reach `map`.
+    pub generations: usize,
+}
+
+impl fmt::Display for SimulationResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.settlement {
+            Settlement::Stable => write!(f, "stabilized after {} generations", self.generations),
+            Settlement::Cycle { period } => write!(
+                f,
+                "cycled with period {} after {} generations",
+                period, self.generations
+            ),
+            Settlement::LimitReached => {
+                write!(f, "hit the limit of {} generations", self.generations)
+            }
+        }
+    }
+}
+
+/// Hashes whether each seat is occupied.
+fn hash_state(state: &[bool]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
 /// Defines where seats are and whether they are occupied.
 #[derive(PartialEq)]
 pub struct SeatingMap {
@@ -18,15 +137,9 @@ pub struct SeatingMap {
 impl SeatingMap {
     /// Creates a seating map of only floors with width `w` and height `h`.
     pub fn new(w: usize, h: usize) -> Self {
-        let mut grid = Vec::new();
-        for _ in 0..h {
-            let mut row = Vec::new();
-            for _ in 0..w {
-                row.push(SeatingTile::Floor);
-            }
-            grid.push(row);
+        Self {
+            grid: vec![vec![SeatingTile::Floor; w]; h],
         }
-        Self { grid: grid }
     }
 
     /// Sets the tile at position (`x`, `y`).
@@ -43,90 +156,37 @@ impl SeatingMap {
         self.grid[y][x]
     }
 
-    /// Counts the number of adjacent seats that are occupied.
-    fn count_adj_filled(&self, x: usize, y: usize) -> usize {
-        let w = self.width();
-        let h = self.height();
-        if x >= w || y >= h {
-            // Out of bounds. Do nothing.
-            return 0;
-        }
-        let mut cnt = 0;
-        for dx in 0..3 {
-            for dy in 0..3 {
-                if (dx == 1 && dy == 1)
-                    || (dx == 0 && x == 0)
-                    || (dx == 2 && x == w - 1)
-                    || (dy == 0 && y == 0)
-                    || (dy == 2 && y == h - 1)
-                {
-                    continue;
-                }
-                match self.get_tile(x + dx - 1, y + dy - 1) {
-                    SeatingTile::FilledSeat => cnt += 1,
-                    _ => {}
-                }
+    /// Finds the first seat visible from (`x`, `y`) when looking along (`dx`, `dy`) for
+    /// at most `max_distance` tiles.
+    fn first_visible_seat(
+        &self,
+        x: usize,
+        y: usize,
+        (dx, dy): (isize, isize),
+        max_distance: Option<usize>,
+    ) -> Option<(usize, usize)> {
+        let w = self.width() as isize;
+        let h = self.height() as isize;
+        let (mut new_x, mut new_y) = (x as isize, y as isize);
+        let mut steps = 0;
+        while max_distance.is_none_or(|max| steps < max) {
+            new_x += dx;
+            new_y += dy;
+            steps += 1;
+            if new_x < 0 || new_y < 0 || new_x >= w || new_y >= h {
+                break;
             }
-        }
-        cnt
-    }
-
-    /// Counts the number of seats within a seat's sightline that are occupied.
-    fn count_sightline_filled(&self, x: usize, y: usize) -> usize {
-        let w = self.width();
-        let h = self.height();
-        if x >= w || y >= h {
-            // Out of bounds. Do nothing.
-            return 0;
-        }
-        let mut cnt = 0;
-        for dx in 0..3 {
-            for dy in 0..3 {
-                if (dx == 1 && dy == 1)
-                    || (dx == 0 && x == 0)
-                    || (dx == 2 && x == w - 1)
-                    || (dy == 0 && y == 0)
-                    || (dy == 2 && y == h - 1)
-                {
-                    continue;
-                }
-                // Look down this direction until a wall or seat is hit.
-                // This would probably be simpler if we used signed integers for coordinates.
-                let num_steps_x = match dx {
-                    0 => x,
-                    1 => usize::MAX,
-                    2 => w - x - 1,
-                    _ => continue,
-                };
-                let num_steps_y = match dy {
-                    0 => y,
-                    1 => usize::MAX,
-                    2 => h - y - 1,
-                    _ => continue,
-                };
-                let num_steps = min(num_steps_x, num_steps_y);
-                let mut new_x = x;
-                let mut new_y = y;
-                for step in 1..num_steps + 1 {
-                    new_x = new_x + dx - 1;
-                    new_y = new_y + dy - 1;
-                    match self.get_tile(new_x, new_y) {
-                        SeatingTile::FilledSeat => {
-                            cnt += 1;
-                            break;
-                        }
-                        SeatingTile::EmptySeat => break,
-                        _ => {}
-                    }
-                }
+            let (seat_x, seat_y) = (new_x as usize, new_y as usize);
+            if self.get_tile(seat_x, seat_y) != SeatingTile::Floor {
+                return Some((seat_x, seat_y));
             }
         }
-        cnt
+        None
     }
 
     /// Gets the width of the seating map.
     pub fn width(&self) -> usize {
-        if self.grid.len() == 0 {
+        if self.grid.is_empty() {
             0
         } else {
             self.grid[0].len()
@@ -138,6 +198,16 @@ impl SeatingMap {
         self.grid.len()
     }
 
+    /// Gets the character for the tile at position (`x`, `y`), as used in puzzle
+    /// inputs.
+    pub fn tile_char(&self, x: usize, y: usize) -> char {
+        match self.get_tile(x, y) {
+            SeatingTile::Floor => '.',
+            SeatingTile::EmptySeat => 'L',
+            SeatingTile::FilledSeat => '#',
+        }
+    }
+
     /// Adds an empty seat at position (`x`, `y`).
     pub fn add_seat(&mut self, x: usize, y: usize) {
         self.set_tile(x, y, SeatingTile::EmptySeat);
@@ -148,85 +218,227 @@ impl SeatingMap {
         let mut cnt = 0;
         for row in self.grid.iter() {
             for tile in row.iter() {
-                match tile {
-                    SeatingTile::FilledSeat => cnt += 1,
-                    _ => {}
+                if *tile == SeatingTile::FilledSeat {
+                    cnt += 1;
                 }
             }
         }
         cnt
     }
 
-    /// Moves seating occupants around based on directly adjacent seating.
-    pub fn next_adj(&self) -> Self {
-        let mut next_map = self.clone();
+    /// Builds the graph of which seats can see each other under `rules`.
+    pub fn visibility_graph(&self, rules: &SeatingRules) -> VisibilityGraph {
+        let w = self.width();
+        let mut seats = Vec::new();
+        let mut seat_ids = vec![None; w * self.height()];
         for y in 0..self.height() {
-            for x in 0..self.width() {
-                match self.get_tile(x, y) {
-                    SeatingTile::FilledSeat => {
-                        if self.count_adj_filled(x, y) >= 4 {
-                            next_map.set_tile(x, y, SeatingTile::EmptySeat)
-                        }
-                    }
-                    SeatingTile::EmptySeat => {
-                        if self.count_adj_filled(x, y) == 0 {
-                            next_map.set_tile(x, y, SeatingTile::FilledSeat)
-                        }
-                    }
-                    _ => {}
+            for x in 0..w {
+                if self.get_tile(x, y) != SeatingTile::Floor {
+                    seat_ids[y * w + x] = Some(seats.len());
+                    seats.push((x, y));
                 }
             }
         }
-        next_map
-    }
 
-    /// Shuffles seat occupants around using the `next_adj` method until seat positions
-    /// no longer change.
-    pub fn get_stable_adj(&self) -> Self {
-        let mut prev = self.next_adj();
-        loop {
-            let next = prev.next_adj();
-            if next == prev {
-                return prev;
+        let mut starts = Vec::with_capacity(seats.len() + 1);
+        let mut neighbors = Vec::new();
+        for (x, y) in seats.iter() {
+            starts.push(neighbors.len());
+            for dir in rules.neighborhood.directions().iter() {
+                if let Some((seat_x, seat_y)) =
+                    self.first_visible_seat(*x, *y, *dir, rules.max_distance)
+                {
+                    // Every visible position is a seat, so it always has an ID.
+                    neighbors.push(seat_ids[seat_y * w + seat_x].unwrap());
+                }
             }
-            prev = next;
+        }
+        starts.push(neighbors.len());
+
+        VisibilityGraph {
+            seats,
+            starts,
+            neighbors,
+            tolerance: rules.tolerance,
         }
     }
 
-    /// Moves seating occupants around based on seats in their sightline.
-    pub fn next_sightline(&self) -> Self {
-        let mut next_map = self.clone();
-        for y in 0..self.height() {
-            for x in 0..self.width() {
-                match self.get_tile(x, y) {
-                    SeatingTile::FilledSeat => {
-                        if self.count_sightline_filled(x, y) >= 5 {
-                            next_map.set_tile(x, y, SeatingTile::EmptySeat)
-                        }
-                    }
-                    SeatingTile::EmptySeat => {
-                        if self.count_sightline_filled(x, y) == 0 {
-                            next_map.set_tile(x, y, SeatingTile::FilledSeat)
-                        }
-                    }
-                    _ => {}
+    /// Gets whether each seat in `graph` is occupied.
+    pub fn seat_state(&self, graph: &VisibilityGraph) -> Vec<bool> {
+        graph
+            .seats
+            .iter()
+            .map(|(x, y)| self.get_tile(*x, *y) == SeatingTile::FilledSeat)
+            .collect()
+    }
+
+    /// Creates a copy of the map with the seats in `graph` occupied according to
+    /// `state`.
+    pub fn with_seat_state(&self, graph: &VisibilityGraph, state: &[bool]) -> Self {
+        let mut map = self.clone();
+        for ((x, y), filled) in graph.seats.iter().zip(state.iter()) {
+            let tile = if *filled {
+                SeatingTile::FilledSeat
+            } else {
+                SeatingTile::EmptySeat
+            };
+            map.set_tile(*x, *y, tile);
+        }
+        map
+    }
+
+    /// Moves seat occupants around for one generation according to `rules`. Empty
+    /// seats with no visible occupants become occupied, and occupants who can see at
+    /// least `rules.tolerance` other occupants leave.
+    #[allow(dead_code)]
+    pub fn step(&self, rules: &SeatingRules) -> Self {
+        let graph = self.visibility_graph(rules);
+        let state = self.seat_state(&graph);
+        let mut next = state.clone();
+        graph.step(&state, &mut next);
+        self.with_seat_state(&graph, &next)
+    }
+
+    /// Shuffles seat occupants around according to `rules` until seat positions no
+    /// longer change, start repeating, or `max_generations` generations have passed.
+    /// `on_generation` is called with the generation number, visibility graph, and
+    /// seat state for the starting generation and every generation after it.
+    pub fn simulate<F>(
+        &self,
+        rules: &SeatingRules,
+        max_generations: usize,
+        mut on_generation: F,
+    ) -> SimulationResult
+    where
+        F: FnMut(usize, &VisibilityGraph, &[bool]),
+    {
+        let graph = self.visibility_graph(rules);
+        let mut state = self.seat_state(&graph);
+        let start = state.clone();
+        let mut next = state.clone();
+        // Maps state hashes to the generation they were first seen. On a hash hit the
+        // earlier state is rebuilt from `start` so a collision isn't taken as a cycle.
+        let mut history: HashMap<u64, usize> = HashMap::new();
+        let mut settlement = Settlement::LimitReached;
+        let mut generations = 0;
+        on_generation(generations, &graph, &state);
+        while generations < max_generations {
+            let hash = hash_state(&state);
+            match history.get(&hash) {
+                Some(prev) if graph.state_after(&start, *prev) == state => {
+                    settlement = Settlement::Cycle {
+                        period: generations - prev,
+                    };
+                    break;
                 }
+                Some(_) => {}
+                None => {
+                    history.insert(hash, generations);
+                }
+            }
+            if !graph.step(&state, &mut next) {
+                settlement = Settlement::Stable;
+                break;
             }
+            std::mem::swap(&mut state, &mut next);
+            generations += 1;
+            on_generation(generations, &graph, &state);
+        }
+        SimulationResult {
+            map: self.with_seat_state(&graph, &state),
+            settlement,
+            generations,
         }
-        next_map
     }
 
-    /// Shuffles seat occupants around using the `next_sightline` method until seat
-    /// positions no longer change.
-    pub fn get_stable_sightline(&self) -> Self {
-        let mut prev = self.next_sightline();
-        loop {
-            let next = prev.next_sightline();
-            if next == prev {
-                return prev;
-            }
-            prev = next;
+    /// Shuffles seat occupants around according to `rules` like `simulate`, without
+    /// watching each generation.
+    pub fn get_stable(&self, rules: &SeatingRules, max_generations: usize) -> SimulationResult {
+        self.simulate(rules, max_generations, |_, _, _| {})
+    }
+
+    /// Shuffles seat occupants around based on directly adjacent seating until seat
+    /// positions settle.
+    #[allow(dead_code)]
+    pub fn get_stable_adj(&self, max_generations: usize) -> SimulationResult {
+        self.get_stable(&SeatingRules::adjacent(), max_generations)
+    }
+
+    /// Shuffles seat occupants around based on seats in their sightline until seat
+    /// positions settle.
+    #[allow(dead_code)]
+    pub fn get_stable_sightline(&self, max_generations: usize) -> SimulationResult {
+        self.get_stable(&SeatingRules::sightline(), max_generations)
+    }
+}
+
+/// Which seats can see each other in a seating map. Visibility only depends on where
+/// seats are, so it can be computed once and reused for every generation.
+pub struct VisibilityGraph {
+    /// Position of each seat, indexed by seat ID.
+    seats: Vec<(usize, usize)>,
+    /// The seats visible from seat `i` are `neighbors[starts[i]..starts[i + 1]]`.
+    starts: Vec<usize>,
+    neighbors: Vec<usize>,
+    /// Number of visible occupied seats at which an occupant leaves their seat.
+    tolerance: usize,
+}
+
+impl VisibilityGraph {
+    /// Gets the number of seats in the graph.
+    pub fn num_seats(&self) -> usize {
+        self.seats.len()
+    }
+
+    /// Gets the position of a seat.
+    pub fn seat_position(&self, seat: usize) -> (usize, usize) {
+        self.seats[seat]
+    }
+
+    /// Gets the IDs of the seats visible from a seat.
+    fn visible_from(&self, seat: usize) -> &[usize] {
+        &self.neighbors[self.starts[seat]..self.starts[seat + 1]]
+    }
+
+    /// Moves seat occupants around for one generation. Empty seats with no visible
+    /// occupants become occupied, and occupants who can see at least the tolerance of
+    /// other occupants leave.
+    ///
+    /// # Arguments
+    ///
+    ///  * `state` - Whether each seat is occupied.
+    ///  * `next` - Filled with whether each seat is occupied in the next generation.
+    ///
+    /// # Returns
+    ///
+    /// Whether any seat changed.
+    pub fn step(&self, state: &[bool], next: &mut [bool]) -> bool {
+        let mut changed = false;
+        for seat in 0..self.num_seats() {
+            let visible = self
+                .visible_from(seat)
+                .iter()
+                .filter(|other| state[**other])
+                .count();
+            next[seat] = if state[seat] {
+                visible < self.tolerance
+            } else {
+                visible == 0
+            };
+            changed |= next[seat] != state[seat];
+        }
+        changed
+    }
+
+    /// Gets the seat state `generations` generations after `start`.
+    fn state_after(&self, start: &[bool], generations: usize) -> Vec<bool> {
+        let mut state = start.to_vec();
+        let mut next = state.clone();
+        for _ in 0..generations {
+            self.step(&state, &mut next);
+            std::mem::swap(&mut state, &mut next);
         }
+        state
     }
 }
 
@@ -236,6 +448,6 @@ impl Clone for SeatingMap {
         for row in self.grid.iter() {
             grid.push(row.clone());
         }
-        Self { grid: grid }
+        Self { grid }
     }
 }
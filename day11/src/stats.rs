@@ -0,0 +1,127 @@
+//! Provides per-seat statistics for seating simulations.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::seating_map::VisibilityGraph;
+
+/// RGB colour of floor tiles in heatmaps.
+const FLOOR_RGB: [u8; 3] = [0, 0, 0];
+/// RGB colour of seats that never changed in heatmaps.
+const CALM_RGB: [u8; 3] = [40, 60, 200];
+/// RGB colour of the seats that changed most in heatmaps.
+const VOLATILE_RGB: [u8; 3] = [230, 40, 30];
+
+/// What happened to a single seat over a simulation.
+pub struct SeatStats {
+    pub x: usize,
+    pub y: usize,
+    /// Number of times the seat went from empty to occupied or back.
+    pub flips: usize,
+    /// The last generation the seat changed in, or 0 if it never changed.
+    pub settled_generation: usize,
+    /// Whether the seat was occupied at the end of the simulation.
+    pub filled: bool,
+}
+
+/// Records per-seat statistics from the generations of a simulation.
+pub struct StatsRecorder {
+    prev: Vec<bool>,
+    stats: Vec<SeatStats>,
+}
+
+impl StatsRecorder {
+    /// Creates a recorder that has not seen any generations.
+    pub fn new() -> Self {
+        Self {
+            prev: Vec::new(),
+            stats: Vec::new(),
+        }
+    }
+
+    /// Records a generation. Generations must be recorded one after another, starting
+    /// from generation 0.
+    pub fn record(&mut self, generation: usize, graph: &VisibilityGraph, state: &[bool]) {
+        if generation == 0 {
+            self.stats = (0..graph.num_seats())
+                .map(|seat| {
+                    let (x, y) = graph.seat_position(seat);
+                    SeatStats {
+                        x,
+                        y,
+                        flips: 0,
+                        settled_generation: 0,
+                        filled: state[seat],
+                    }
+                })
+                .collect();
+        } else {
+            for (seat, stats) in self.stats.iter_mut().enumerate() {
+                if state[seat] != self.prev[seat] {
+                    stats.flips += 1;
+                    stats.settled_generation = generation;
+                    stats.filled = state[seat];
+                }
+            }
+        }
+        self.prev = state.to_vec();
+    }
+
+    /// Gets the statistics for every seat recorded so far.
+    pub fn stats(&self) -> &[SeatStats] {
+        &self.stats
+    }
+}
+
+/// Writes per-seat statistics as CSV.
+///
+/// # Arguments
+///
+///  * `path` - Path of the CSV file to write.
+///  * `stats` - Statistics for each seat.
+pub fn write_csv<P>(path: P, stats: &[SeatStats]) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "x,y,flips,settled_generation,filled")?;
+    for seat in stats.iter() {
+        writeln!(
+            out,
+            "{},{},{},{},{}",
+            seat.x, seat.y, seat.flips, seat.settled_generation, seat.filled as u8
+        )?;
+    }
+    out.flush()
+}
+
+/// Writes a heatmap of how often each seat flipped as a binary PPM image, one pixel
+/// per tile. Seats are shaded from blue for seats that never changed to red for the
+/// most volatile seats, and floors are black.
+///
+/// # Arguments
+///
+///  * `path` - Path of the PPM file to write.
+///  * `width` - Width of the seating map.
+///  * `height` - Height of the seating map.
+///  * `stats` - Statistics for each seat.
+pub fn write_heatmap<P>(path: P, width: usize, height: usize, stats: &[SeatStats]) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let max_flips = stats.iter().map(|s| s.flips).max().unwrap_or(0).max(1);
+    let mut pixels = FLOOR_RGB.repeat(width * height);
+    for seat in stats.iter() {
+        let t = seat.flips as f64 / max_flips as f64;
+        let i = 3 * (seat.y * width + seat.x);
+        for c in 0..3 {
+            let (calm, volatile) = (CALM_RGB[c] as f64, VOLATILE_RGB[c] as f64);
+            pixels[i + c] = (calm + (volatile - calm) * t).round() as u8;
+        }
+    }
+    let mut out = BufWriter::new(File::create(path)?);
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    out.write_all(&pixels)?;
+    out.flush()
+}
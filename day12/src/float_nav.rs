@@ -0,0 +1,197 @@
+//! Provides ship navigation with floating-point positions, allowing rotations by any
+//! angle.
+
+use crate::trajectory::Trajectory;
+use crate::{altitude_change, InstrType, Instruction, NavConfig, RotateDir, StrafeDir};
+
+/// How floating-point positions are rounded after every instruction.
+#[derive(Clone, Copy, Debug)]
+pub enum Rounding {
+    /// Keep full precision.
+    None,
+    /// Round to the nearest integer.
+    Nearest,
+    /// Round to a number of decimal places.
+    Decimals(u32),
+}
+
+/// Most decimal places positions can be rounded to. An `f64` holds at most 15
+/// significant decimal digits, and larger scales overflow to infinity.
+pub const MAX_DECIMALS: u32 = 15;
+
+impl Rounding {
+    /// Parses a rounding mode from `none`, `nearest`, or a number of decimal places up
+    /// to `MAX_DECIMALS`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Self::None),
+            "nearest" => Some(Self::Nearest),
+            _ => match name.parse() {
+                Ok(places) if places <= MAX_DECIMALS => Some(Self::Decimals(places)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Rounds a value.
+    fn apply(&self, val: f64) -> f64 {
+        match self {
+            Self::None => val,
+            Self::Nearest => val.round(),
+            Self::Decimals(places) => {
+                let scale = 10f64.powi(*places as i32);
+                (val * scale).round() / scale
+            }
+        }
+    }
+
+    /// Rounds every coordinate of a point.
+    fn apply_point(&self, (x, y, z): (f64, f64, f64)) -> (f64, f64, f64) {
+        (self.apply(x), self.apply(y), self.apply(z))
+    }
+}
+
+/// Gets the unit vector for a strafe direction.
+fn strafe_vec(dir: &StrafeDir) -> (f64, f64) {
+    match dir {
+        StrafeDir::North => (0.0, 1.0),
+        StrafeDir::East => (1.0, 0.0),
+        StrafeDir::South => (0.0, -1.0),
+        StrafeDir::West => (-1.0, 0.0),
+    }
+}
+
+/// Gets the counterclockwise rotation in degrees for a rotate instruction.
+fn rotation_degrees(dir: &RotateDir, val: i32) -> f64 {
+    match dir {
+        RotateDir::Left => val as f64,
+        RotateDir::Right => -val as f64,
+    }
+}
+
+/// Rotates a point (`x`, `y`) `degree` degrees counterclockwise about the origin.
+fn rotate_point_f64(x: f64, y: f64, degree: f64) -> (f64, f64) {
+    let (sin, cos) = degree.to_radians().sin_cos();
+    (x * cos - y * sin, x * sin + y * cos)
+}
+
+/// Executes the ship's movement instructions with floating-point positions and returns
+/// the final position difference as east, north, and altitude. Teleport instructions
+/// are ignored since there is no waypoint.
+///
+/// # Arguments
+///
+///  * `ins` - Instructions to execute.
+///  * `config` - Starting state for navigation.
+///  * `rounding` - Rounding applied to the ship's position after every instruction.
+///  * `traj` - Records the ship's position after every instruction, if given.
+pub fn exec_ins_ship_float(
+    ins: &[Instruction],
+    config: &NavConfig,
+    rounding: Rounding,
+    mut traj: Option<&mut Trajectory>,
+) -> (f64, f64, f64) {
+    let mut pos = (0.0, 0.0, 0.0);
+    if let Some(t) = traj.as_deref_mut() {
+        t.record_ship(pos.0, pos.1);
+    }
+    // Heading in degrees counterclockwise from east.
+    let mut heading = (90.0 - config.heading as f64).rem_euclid(360.0);
+    for i in ins.iter() {
+        let val = i.val as f64;
+        match &i.typ {
+            InstrType::Strafe(dir) => {
+                let (dx, dy) = strafe_vec(dir);
+                pos = (pos.0 + dx * val, pos.1 + dy * val, pos.2);
+            }
+            InstrType::Rotate(dir) => {
+                heading = (heading + rotation_degrees(dir, i.val)).rem_euclid(360.0);
+            }
+            InstrType::Forward => {
+                let (dx, dy) = rotate_point_f64(1.0, 0.0, heading);
+                pos = (pos.0 + dx * val, pos.1 + dy * val, pos.2);
+            }
+            InstrType::Backward => {
+                let (dx, dy) = rotate_point_f64(1.0, 0.0, heading);
+                pos = (pos.0 - dx * val, pos.1 - dy * val, pos.2);
+            }
+            InstrType::Altitude(dir) => pos.2 += altitude_change(dir, val),
+            InstrType::Teleport(..) => {}
+        }
+        pos = rounding.apply_point(pos);
+        if let Some(t) = traj.as_deref_mut() {
+            t.record_ship(pos.0, pos.1);
+        }
+    }
+    pos
+}
+
+/// Records the ship's and waypoint's positions in `traj`, if given.
+fn record_waypoint_state(
+    traj: &mut Option<&mut Trajectory>,
+    ship: (f64, f64, f64),
+    wp: (f64, f64, f64),
+) {
+    if let Some(t) = traj.as_deref_mut() {
+        t.record_ship(ship.0, ship.1);
+        t.record_waypoint(ship.0 + wp.0, ship.1 + wp.1);
+    }
+}
+
+/// Executes the ship's movement instructions using the waypoint method with
+/// floating-point positions and returns the final position difference as east, north,
+/// and altitude.
+///
+/// # Arguments
+///
+///  * `ins` - Instructions to execute.
+///  * `config` - Starting state for navigation.
+///  * `rounding` - Rounding applied to the ship's and waypoint's positions after every
+///    instruction.
+///  * `traj` - Records the ship's and waypoint's positions after every instruction, if
+///    given.
+pub fn exec_ins_waypoint_float(
+    ins: &[Instruction],
+    config: &NavConfig,
+    rounding: Rounding,
+    mut traj: Option<&mut Trajectory>,
+) -> (f64, f64, f64) {
+    let mut ship = (0.0, 0.0, 0.0);
+    // Position of the waypoint relative to the ship.
+    let (wp_x, wp_y, wp_z) = config.waypoint;
+    let mut wp = (wp_x as f64, wp_y as f64, wp_z as f64);
+    record_waypoint_state(&mut traj, ship, wp);
+    for i in ins.iter() {
+        let val = i.val as f64;
+        match &i.typ {
+            InstrType::Strafe(dir) => {
+                let (dx, dy) = strafe_vec(dir);
+                wp = (wp.0 + dx * val, wp.1 + dy * val, wp.2);
+            }
+            InstrType::Rotate(dir) => {
+                let (x, y) = rotate_point_f64(wp.0, wp.1, rotation_degrees(dir, i.val));
+                wp = (x, y, wp.2);
+            }
+            InstrType::Forward => {
+                ship = (
+                    ship.0 + wp.0 * val,
+                    ship.1 + wp.1 * val,
+                    ship.2 + wp.2 * val,
+                );
+            }
+            InstrType::Backward => {
+                ship = (
+                    ship.0 - wp.0 * val,
+                    ship.1 - wp.1 * val,
+                    ship.2 - wp.2 * val,
+                );
+            }
+            InstrType::Altitude(dir) => wp.2 += altitude_change(dir, val),
+            InstrType::Teleport(x, y) => wp = (*x as f64, *y as f64, wp.2),
+        }
+        ship = rounding.apply_point(ship);
+        wp = rounding.apply_point(wp);
+        record_waypoint_state(&mut traj, ship, wp);
+    }
+    ship
+}
@@ -1,10 +1,20 @@
+mod float_nav;
+mod planning;
+mod trajectory;
+
 use std::convert::TryFrom;
 use std::env;
-use std::path::Path;
+use std::fmt;
+use std::fs;
+use std::ops::Neg;
+use std::path::{Path, PathBuf};
 use std::process;
-use std::usize;
 use utils::read_lines;
 
+use float_nav::{exec_ins_ship_float, exec_ins_waypoint_float, Rounding, MAX_DECIMALS};
+use planning::{compact_ship, compact_waypoint, reverse_ship, reverse_waypoint};
+use trajectory::{write_svg, Trajectory};
+
 /// Directions ship can strafe in.
 #[derive(Clone, Copy)]
 enum StrafeDir {
@@ -21,12 +31,24 @@ enum RotateDir {
     Right,
 }
 
+/// Directions ship can change altitude in.
+#[derive(Clone, Copy)]
+enum AltitudeDir {
+    Up,
+    Down,
+}
+
 /// Types of movement instructions for a ship.
 #[derive(Clone, Copy)]
 enum InstrType {
     Strafe(StrafeDir),
     Rotate(RotateDir),
     Forward,
+    Backward,
+    Altitude(AltitudeDir),
+    /// Moves the waypoint to an east and north offset from the ship, keeping its
+    /// altitude.
+    Teleport(i32, i32),
 }
 
 /// An instruction type it's magnitude.
@@ -36,9 +58,155 @@ struct Instruction {
     pub val: i32,
 }
 
+impl Instruction {
+    /// Creates an instruction.
+    fn new(typ: InstrType, val: i32) -> Self {
+        Self { typ, val }
+    }
+
+    /// Checks whether the instruction can be executed exactly with integer positions,
+    /// i.e. it is not a rotation by something other than a multiple of 90 degrees.
+    fn is_right_angled(&self) -> bool {
+        match self.typ {
+            InstrType::Rotate(_) => self.val % 90 == 0,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ch = match self.typ {
+            InstrType::Strafe(StrafeDir::North) => 'N',
+            InstrType::Strafe(StrafeDir::East) => 'E',
+            InstrType::Strafe(StrafeDir::South) => 'S',
+            InstrType::Strafe(StrafeDir::West) => 'W',
+            InstrType::Rotate(RotateDir::Left) => 'L',
+            InstrType::Rotate(RotateDir::Right) => 'R',
+            InstrType::Forward => 'F',
+            InstrType::Backward => 'B',
+            InstrType::Altitude(AltitudeDir::Up) => 'U',
+            InstrType::Altitude(AltitudeDir::Down) => 'D',
+            InstrType::Teleport(x, y) => return write!(f, "T{},{}", x, y),
+        };
+        write!(f, "{}{}", ch, self.val)
+    }
+}
+
+/// Starting state for navigation.
+struct NavConfig {
+    /// Compass heading the ship starts facing, in degrees clockwise from north.
+    heading: i32,
+    /// Starting east, north, and altitude offset of the waypoint from the ship.
+    waypoint: (i32, i32, i32),
+}
+
+impl NavConfig {
+    /// Gets the starting state from the original puzzle.
+    fn new() -> Self {
+        Self {
+            heading: 90,
+            waypoint: (10, 1, 0),
+        }
+    }
+}
+
+/// Parses a compass heading from a cardinal direction or degrees clockwise from north.
+fn parse_heading(s: &str) -> Option<i32> {
+    match s {
+        "N" => Some(0),
+        "E" => Some(90),
+        "S" => Some(180),
+        "W" => Some(270),
+        _ => s.parse().ok(),
+    }
+}
+
+/// Parses a waypoint offset of the form `east,north[,altitude]`.
+fn parse_waypoint(s: &str) -> Option<(i32, i32, i32)> {
+    let nums: Vec<i32> = s
+        .split(',')
+        .map(|n| n.trim().parse().ok())
+        .collect::<Option<Vec<i32>>>()?;
+    match nums.as_slice() {
+        [x, y] => Some((*x, *y, 0)),
+        [x, y, z] => Some((*x, *y, *z)),
+        _ => None,
+    }
+}
+
+/// Command line options.
+struct Options {
+    input_path: String,
+    /// Whether to always navigate with floating-point positions.
+    float: bool,
+    /// Rounding applied to floating-point positions after every instruction.
+    rounding: Rounding,
+    /// Directory to write SVG images of the routes to, if any.
+    svg_dir: Option<PathBuf>,
+    /// Starting state for navigation.
+    config: NavConfig,
+    /// Whether to print routes that undo the instructions.
+    reverse: bool,
+    /// Whether to print shortened routes equivalent to the instructions.
+    compact: bool,
+}
+
 /// Prints usage statement for the executable.
 fn usage(args: Vec<String>) {
-    println!("Usage: {} input_file scope", args[0]);
+    println!(
+        "Usage: {} input_file [--float] [--rounding none|nearest|DECIMALS] [--svg DIR] [--heading N|E|S|W|DEGREES] [--waypoint X,Y[,Z]] [--reverse] [--compact]",
+        args[0]
+    );
+}
+
+/// Parses command line arguments.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let input_path = match args.get(1) {
+        Some(p) => p.clone(),
+        None => return Err("Missing input file".into()),
+    };
+    let mut opts = Options {
+        input_path,
+        float: false,
+        rounding: Rounding::None,
+        svg_dir: None,
+        config: NavConfig::new(),
+        reverse: false,
+        compact: false,
+    };
+    let mut iter = args.iter().skip(2);
+    while let Some(flag) = iter.next() {
+        match flag.as_str() {
+            "--float" => opts.float = true,
+            "--reverse" => opts.reverse = true,
+            "--compact" => opts.compact = true,
+            "--rounding" => {
+                let value = match iter.next() {
+                    Some(v) => v,
+                    None => return Err("Missing value for --rounding".into()),
+                };
+                opts.rounding = Rounding::from_name(value).ok_or(format!(
+                    "Invalid rounding: {}, expected none, nearest, or 0 to {} decimal places",
+                    value, MAX_DECIMALS
+                ))?;
+            }
+            "--svg" => match iter.next() {
+                Some(dir) => opts.svg_dir = Some(PathBuf::from(dir)),
+                None => return Err("Missing value for --svg".into()),
+            },
+            "--heading" => match iter.next().and_then(|h| parse_heading(h)) {
+                Some(heading) => opts.config.heading = heading,
+                None => return Err("Expected N, E, S, W, or degrees for --heading".into()),
+            },
+            "--waypoint" => match iter.next().and_then(|w| parse_waypoint(w)) {
+                Some(waypoint) => opts.config.waypoint = waypoint,
+                None => return Err("Expected X,Y or X,Y,Z for --waypoint".into()),
+            },
+            _ => return Err(format!("Unexpected argument: {}", flag)),
+        }
+    }
+    Ok(opts)
 }
 
 /// Reads instructions from a file.
@@ -65,7 +233,22 @@ where
         } else if line.len() <= 1 {
             return Err(format!("Line {} is too short", i + 1));
         }
-        let ins_char = line.chars().nth(0).unwrap();
+        let ins_char = line.chars().next().unwrap();
+        if ins_char == 'T' {
+            let coords: Option<Vec<i32>> = line[1..]
+                .split(',')
+                .map(|n| n.trim().parse().ok())
+                .collect();
+            let (x, y) = match coords.as_deref() {
+                Some([x, y]) => (*x, *y),
+                _ => return Err(format!("Failed to parse teleport on line {}", i + 1)),
+            };
+            ins.push(Instruction {
+                typ: InstrType::Teleport(x, y),
+                val: 0,
+            });
+            continue;
+        }
         let typ = match ins_char {
             'N' => InstrType::Strafe(StrafeDir::North),
             'E' => InstrType::Strafe(StrafeDir::East),
@@ -74,6 +257,9 @@ where
             'L' => InstrType::Rotate(RotateDir::Left),
             'R' => InstrType::Rotate(RotateDir::Right),
             'F' => InstrType::Forward,
+            'B' => InstrType::Backward,
+            'U' => InstrType::Altitude(AltitudeDir::Up),
+            'D' => InstrType::Altitude(AltitudeDir::Down),
             _ => {
                 return Err(format!(
                     "Invalid instruction type \"{}\" on line {}",
@@ -86,55 +272,103 @@ where
             Ok(l) => l,
             Err(_) => return Err(format!("Failed to number on line {}", i + 1)),
         };
-        ins.push(Instruction { typ: typ, val: val });
+        ins.push(Instruction { typ, val });
     }
     Ok(ins)
 }
 
-/// Executes the ship's movement instructions and returns the final position difference.
-/// Ship begins pointing east.
-fn exec_ins_ship(ins: &Vec<Instruction>) -> (i32, i32) {
-    let mut x: i32 = 0;
-    let mut y: i32 = 0;
-    let ship_dirs: [StrafeDir; 4] = [
-        StrafeDir::North,
-        StrafeDir::East,
-        StrafeDir::South,
-        StrafeDir::West,
-    ];
-    // Ship starts by facing east.
-    let mut rot_i: usize = 1;
-    for i in ins.iter() {
+/// Gets the signed change in altitude for an altitude instruction.
+fn altitude_change<T: Neg<Output = T>>(dir: &AltitudeDir, val: T) -> T {
+    match dir {
+        AltitudeDir::Up => val,
+        AltitudeDir::Down => -val,
+    }
+}
+
+/// Directions the ship can face, in clockwise order.
+const SHIP_DIRS: [StrafeDir; 4] = [
+    StrafeDir::North,
+    StrafeDir::East,
+    StrafeDir::South,
+    StrafeDir::West,
+];
+
+/// State of a ship navigating without a waypoint.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct ShipState {
+    /// East, north, and altitude position.
+    pos: (i32, i32, i32),
+    /// Index into `SHIP_DIRS` of the direction the ship is facing.
+    rot_i: usize,
+}
+
+impl ShipState {
+    /// Creates the starting state for a ship.
+    fn new(config: &NavConfig) -> Self {
+        Self {
+            pos: (0, 0, 0),
+            rot_i: usize::try_from((config.heading / 90).rem_euclid(4)).unwrap(),
+        }
+    }
+
+    /// Executes a single instruction. Teleport instructions are ignored since there is
+    /// no waypoint.
+    fn apply(&mut self, i: &Instruction) {
         let mut strafe = None;
+        let mut val = i.val;
         match &i.typ {
             InstrType::Strafe(dir) => {
-                strafe = Some(dir);
+                strafe = Some(*dir);
             }
             InstrType::Rotate(dir) => {
                 let i_diff = match dir {
                     RotateDir::Left => -i.val / 90,
                     RotateDir::Right => i.val / 90,
                 };
-                let mut new_rot_i = (i32::try_from(rot_i).unwrap() + i_diff) % 4;
-                if new_rot_i < 0 {
-                    new_rot_i = 4 + new_rot_i;
-                }
-                rot_i = usize::try_from(new_rot_i).unwrap();
+                let new_rot_i = (i32::try_from(self.rot_i).unwrap() + i_diff).rem_euclid(4);
+                self.rot_i = usize::try_from(new_rot_i).unwrap();
             }
             InstrType::Forward => {
-                strafe = Some(&ship_dirs[rot_i]);
+                strafe = Some(SHIP_DIRS[self.rot_i]);
+            }
+            InstrType::Backward => {
+                strafe = Some(SHIP_DIRS[self.rot_i]);
+                val = -val;
             }
+            InstrType::Altitude(dir) => self.pos.2 += altitude_change(dir, i.val),
+            InstrType::Teleport(..) => {}
         }
         if let Some(dir) = strafe {
             match dir {
-                StrafeDir::North => y += i.val,
-                StrafeDir::East => x += i.val,
-                StrafeDir::South => y -= i.val,
-                StrafeDir::West => x -= i.val,
+                StrafeDir::North => self.pos.1 += val,
+                StrafeDir::East => self.pos.0 += val,
+                StrafeDir::South => self.pos.1 -= val,
+                StrafeDir::West => self.pos.0 -= val,
             }
         }
     }
-    (x, y)
+}
+
+/// Executes the ship's movement instructions and returns the final position difference
+/// as east, north, and altitude. Teleport instructions are ignored since there is no
+/// waypoint. The ship's position after every instruction is recorded in `traj`, if
+/// given.
+fn exec_ins_ship(
+    ins: &[Instruction],
+    config: &NavConfig,
+    mut traj: Option<&mut Trajectory>,
+) -> (i32, i32, i32) {
+    let mut state = ShipState::new(config);
+    if let Some(t) = traj.as_deref_mut() {
+        t.record_ship(state.pos.0 as f64, state.pos.1 as f64);
+    }
+    for i in ins.iter() {
+        state.apply(i);
+        if let Some(t) = traj.as_deref_mut() {
+            t.record_ship(state.pos.0 as f64, state.pos.1 as f64);
+        }
+    }
+    state.pos
 }
 
 /// Rotates a point (`x`, `y`) `degree` degrees about the origin.
@@ -143,7 +377,7 @@ fn rotate_point(x: i32, y: i32, degree: i32) -> (i32, i32) {
     let sin: [i32; 4] = [0, 1, 0, -1];
     let mut step = (degree / 90) % 4;
     if step < 0 {
-        step = 4 + step;
+        step += 4;
     }
     let i = usize::try_from(step).unwrap();
     let new_x = x * cos[i] - y * sin[i];
@@ -151,48 +385,109 @@ fn rotate_point(x: i32, y: i32, degree: i32) -> (i32, i32) {
     (new_x, new_y)
 }
 
-/// Executes the ship's movement instructions using the waypoing method and returns the
-/// final position difference.
-fn exec_ins_waypoint(ins: &Vec<Instruction>) -> (i32, i32) {
-    let mut ship_x: i32 = 0;
-    let mut ship_y: i32 = 0;
-    // Position of the waypoint relative to the ship.
-    let mut wp_x: i32 = 10;
-    let mut wp_y: i32 = 1;
-    for i in ins.iter() {
+/// State of a ship navigating with a waypoint.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct WaypointState {
+    /// East, north, and altitude position of the ship.
+    ship: (i32, i32, i32),
+    /// East, north, and altitude position of the waypoint relative to the ship.
+    wp: (i32, i32, i32),
+}
+
+impl WaypointState {
+    /// Creates the starting state for a ship and its waypoint.
+    fn new(config: &NavConfig) -> Self {
+        Self {
+            ship: (0, 0, 0),
+            wp: config.waypoint,
+        }
+    }
+
+    /// Executes a single instruction.
+    fn apply(&mut self, i: &Instruction) {
+        let (ship, wp) = (&mut self.ship, &mut self.wp);
         match &i.typ {
             InstrType::Strafe(dir) => match dir {
-                StrafeDir::North => wp_y += i.val,
-                StrafeDir::East => wp_x += i.val,
-                StrafeDir::South => wp_y -= i.val,
-                StrafeDir::West => wp_x -= i.val,
+                StrafeDir::North => wp.1 += i.val,
+                StrafeDir::East => wp.0 += i.val,
+                StrafeDir::South => wp.1 -= i.val,
+                StrafeDir::West => wp.0 -= i.val,
             },
             InstrType::Rotate(dir) => {
                 let degrees = match dir {
                     RotateDir::Left => i.val,
                     RotateDir::Right => -i.val,
                 };
-                let (new_wp_x, new_wp_y) = rotate_point(wp_x, wp_y, degrees);
-                wp_x = new_wp_x;
-                wp_y = new_wp_y;
+                let (new_wp_x, new_wp_y) = rotate_point(wp.0, wp.1, degrees);
+                wp.0 = new_wp_x;
+                wp.1 = new_wp_y;
             }
             InstrType::Forward => {
-                ship_x += wp_x * i.val;
-                ship_y += wp_y * i.val;
+                ship.0 += wp.0 * i.val;
+                ship.1 += wp.1 * i.val;
+                ship.2 += wp.2 * i.val;
+            }
+            InstrType::Backward => {
+                ship.0 -= wp.0 * i.val;
+                ship.1 -= wp.1 * i.val;
+                ship.2 -= wp.2 * i.val;
             }
+            InstrType::Altitude(dir) => wp.2 += altitude_change(dir, i.val),
+            InstrType::Teleport(x, y) => {
+                wp.0 = *x;
+                wp.1 = *y;
+            }
+        }
+    }
+
+    /// Records the ship's and waypoint's positions in `traj`, if given.
+    fn record(&self, traj: &mut Option<&mut Trajectory>) {
+        if let Some(t) = traj.as_deref_mut() {
+            t.record_ship(self.ship.0 as f64, self.ship.1 as f64);
+            t.record_waypoint(
+                (self.ship.0 + self.wp.0) as f64,
+                (self.ship.1 + self.wp.1) as f64,
+            );
         }
     }
-    (ship_x, ship_y)
+}
+
+/// Executes the ship's movement instructions using the waypoing method and returns the
+/// final position difference as east, north, and altitude. The ship's and waypoint's
+/// positions after every instruction are recorded in `traj`, if given.
+fn exec_ins_waypoint(
+    ins: &[Instruction],
+    config: &NavConfig,
+    mut traj: Option<&mut Trajectory>,
+) -> (i32, i32, i32) {
+    let mut state = WaypointState::new(config);
+    state.record(&mut traj);
+    for i in ins.iter() {
+        state.apply(i);
+        state.record(&mut traj);
+    }
+    state.ship
+}
+
+/// Prints a route with one instruction per line, as in input files.
+fn print_route(title: &str, ins: &[Instruction]) {
+    println!("\n==== {} ====", title);
+    for i in ins.iter() {
+        println!("{}", i);
+    }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        usage(args);
-        process::exit(1);
-    }
-    let input_path = String::from(&args[1]);
-    let ins = match read_instructions(&input_path) {
+    let opts = match parse_args(&args) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}", e);
+            usage(args);
+            process::exit(1);
+        }
+    };
+    let ins = match read_instructions(&opts.input_path) {
         Ok(ins) => ins,
         Err(e) => {
             eprintln!("Failed to read instructions: {}", e);
@@ -200,17 +495,88 @@ fn main() {
         }
     };
 
+    // Only fall back on floating-point navigation when rotations require it.
+    let float =
+        opts.float || opts.config.heading % 90 != 0 || !ins.iter().all(|i| i.is_right_angled());
+    if float {
+        println!("Navigating with floating-point positions");
+    }
+
+    if let Some(dir) = &opts.svg_dir {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("Failed to create SVG directory: {}", e);
+            process::exit(1);
+        }
+    }
+    let mut ship_traj = Trajectory::new();
+    let mut waypoint_traj = Trajectory::new();
+    let recording = opts.svg_dir.is_some();
+
     println!("\n==== Part 1 ====");
-    let (final_x, final_y) = exec_ins_ship(&ins);
-    println!(
-        "Final Manhattan distance using ship only: {}",
-        final_x.abs() + final_y.abs()
-    );
+    let traj = Some(&mut ship_traj).filter(|_| recording);
+    if float {
+        let (final_x, final_y, final_z) =
+            exec_ins_ship_float(&ins, &opts.config, opts.rounding, traj);
+        println!(
+            "Final Manhattan distance using ship only: {:.3}",
+            final_x.abs() + final_y.abs() + final_z.abs()
+        );
+    } else {
+        let (final_x, final_y, final_z) = exec_ins_ship(&ins, &opts.config, traj);
+        println!(
+            "Final Manhattan distance using ship only: {}",
+            final_x.abs() + final_y.abs() + final_z.abs()
+        );
+    }
 
     println!("\n==== Part 2 ====");
-    let (final_wp_x, final_wp_y) = exec_ins_waypoint(&ins);
-    println!(
-        "Final Manhattan distance using a waypoint: {}",
-        final_wp_x.abs() + final_wp_y.abs()
-    );
+    let traj = Some(&mut waypoint_traj).filter(|_| recording);
+    if float {
+        let (final_wp_x, final_wp_y, final_wp_z) =
+            exec_ins_waypoint_float(&ins, &opts.config, opts.rounding, traj);
+        println!(
+            "Final Manhattan distance using a waypoint: {:.3}",
+            final_wp_x.abs() + final_wp_y.abs() + final_wp_z.abs()
+        );
+    } else {
+        let (final_wp_x, final_wp_y, final_wp_z) = exec_ins_waypoint(&ins, &opts.config, traj);
+        println!(
+            "Final Manhattan distance using a waypoint: {}",
+            final_wp_x.abs() + final_wp_y.abs() + final_wp_z.abs()
+        );
+    }
+
+    if let Some(dir) = &opts.svg_dir {
+        for (name, traj) in [("ship.svg", &ship_traj), ("waypoint.svg", &waypoint_traj)].iter() {
+            let path = dir.join(name);
+            if let Err(e) = write_svg(&path, traj) {
+                eprintln!("Failed to write {}: {}", path.display(), e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if opts.reverse || opts.compact {
+        if float {
+            eprintln!("Route planning requires rotations by multiples of 90 degrees");
+            process::exit(1);
+        }
+        if opts.reverse {
+            print_route("Return route using ship only", &reverse_ship(&ins));
+            print_route(
+                "Return route using a waypoint",
+                &reverse_waypoint(&ins, &opts.config),
+            );
+        }
+        if opts.compact {
+            print_route(
+                "Compact route using ship only",
+                &compact_ship(&ins, &opts.config),
+            );
+            print_route(
+                "Compact route using a waypoint",
+                &compact_waypoint(&ins, &opts.config),
+            );
+        }
+    }
 }
@@ -0,0 +1,197 @@
+//! Provides planning of routes back to the start and compacting of routes.
+//!
+//! Planning relies on exact integer navigation, so every rotation must be a multiple of
+//! 90 degrees.
+
+use crate::{
+    AltitudeDir, InstrType, Instruction, NavConfig, RotateDir, ShipState, StrafeDir, WaypointState,
+};
+
+/// Gets the instruction that undoes `i`, ignoring any waypoint teleports.
+fn invert(i: &Instruction) -> Instruction {
+    let typ = match i.typ {
+        InstrType::Strafe(StrafeDir::North) => InstrType::Strafe(StrafeDir::South),
+        InstrType::Strafe(StrafeDir::East) => InstrType::Strafe(StrafeDir::West),
+        InstrType::Strafe(StrafeDir::South) => InstrType::Strafe(StrafeDir::North),
+        InstrType::Strafe(StrafeDir::West) => InstrType::Strafe(StrafeDir::East),
+        InstrType::Rotate(RotateDir::Left) => InstrType::Rotate(RotateDir::Right),
+        InstrType::Rotate(RotateDir::Right) => InstrType::Rotate(RotateDir::Left),
+        InstrType::Forward => InstrType::Backward,
+        InstrType::Backward => InstrType::Forward,
+        InstrType::Altitude(AltitudeDir::Up) => InstrType::Altitude(AltitudeDir::Down),
+        InstrType::Altitude(AltitudeDir::Down) => InstrType::Altitude(AltitudeDir::Up),
+        InstrType::Teleport(x, y) => InstrType::Teleport(x, y),
+    };
+    Instruction::new(typ, i.val)
+}
+
+/// Generates the instructions that undo a route navigated without a waypoint, bringing
+/// the ship back to its starting position and heading.
+pub fn reverse_ship(ins: &[Instruction]) -> Vec<Instruction> {
+    ins.iter()
+        .rev()
+        .filter(|i| !matches!(i.typ, InstrType::Teleport(..)))
+        .map(invert)
+        .collect()
+}
+
+/// Generates the instructions that undo a route navigated with a waypoint, bringing the
+/// ship and waypoint back to their starting positions.
+pub fn reverse_waypoint(ins: &[Instruction], config: &NavConfig) -> Vec<Instruction> {
+    let mut state = WaypointState::new(config);
+    let mut reversed = Vec::with_capacity(ins.len());
+    for i in ins.iter() {
+        reversed.push(match i.typ {
+            // Teleporting loses the old waypoint, so teleport back to it.
+            InstrType::Teleport(..) => {
+                Instruction::new(InstrType::Teleport(state.wp.0, state.wp.1), 0)
+            }
+            _ => invert(i),
+        });
+        state.apply(i);
+    }
+    reversed.reverse();
+    reversed
+}
+
+/// Gets the instructions for moving `val` along an axis, using `pos` for positive values
+/// and `neg` for negative values.
+fn axis_move(val: i32, pos: InstrType, neg: InstrType) -> Option<Instruction> {
+    match val {
+        0 => None,
+        v if v > 0 => Some(Instruction::new(pos, v)),
+        v => Some(Instruction::new(neg, -v)),
+    }
+}
+
+/// Gets the rotation from facing `from` to facing `to`, as indices into `SHIP_DIRS`.
+fn rotation_between(from: usize, to: usize) -> Option<Instruction> {
+    match (to + 4 - from) % 4 {
+        0 => None,
+        3 => Some(Instruction::new(InstrType::Rotate(RotateDir::Left), 90)),
+        steps => Some(Instruction::new(
+            InstrType::Rotate(RotateDir::Right),
+            90 * steps as i32,
+        )),
+    }
+}
+
+/// Collapses a route navigated without a waypoint into a shortest equivalent route,
+/// which ends with the ship at the same position and heading. The ship strafes along
+/// each axis it moved along, then turns to its final heading.
+pub fn compact_ship(ins: &[Instruction], config: &NavConfig) -> Vec<Instruction> {
+    let start = ShipState::new(config);
+    let mut end = start;
+    for i in ins.iter() {
+        end.apply(i);
+    }
+    let (x, y, z) = end.pos;
+    vec![
+        axis_move(
+            x,
+            InstrType::Strafe(StrafeDir::East),
+            InstrType::Strafe(StrafeDir::West),
+        ),
+        axis_move(
+            y,
+            InstrType::Strafe(StrafeDir::North),
+            InstrType::Strafe(StrafeDir::South),
+        ),
+        axis_move(
+            z,
+            InstrType::Altitude(AltitudeDir::Up),
+            InstrType::Altitude(AltitudeDir::Down),
+        ),
+        rotation_between(start.rot_i, end.rot_i),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Gets the fewest instructions that change the waypoint from `from` to `to` without
+/// moving the ship.
+fn waypoint_change(from: (i32, i32, i32), to: (i32, i32, i32)) -> Vec<Instruction> {
+    let mut ins = Vec::new();
+    if (from.0, from.1) != (to.0, to.1) {
+        // Try a single rotation, then a single strafe, before falling back on a teleport.
+        let rotation = (1..4)
+            .find(|steps| crate::rotate_point(from.0, from.1, -90 * steps) == (to.0, to.1))
+            .map(|steps| Instruction::new(InstrType::Rotate(RotateDir::Right), 90 * steps));
+        let strafe = if from.0 == to.0 {
+            axis_move(
+                to.1 - from.1,
+                InstrType::Strafe(StrafeDir::North),
+                InstrType::Strafe(StrafeDir::South),
+            )
+        } else if from.1 == to.1 {
+            axis_move(
+                to.0 - from.0,
+                InstrType::Strafe(StrafeDir::East),
+                InstrType::Strafe(StrafeDir::West),
+            )
+        } else {
+            None
+        };
+        ins.push(
+            rotation
+                .or(strafe)
+                .unwrap_or_else(|| Instruction::new(InstrType::Teleport(to.0, to.1), 0)),
+        );
+    }
+    ins.extend(axis_move(
+        to.2 - from.2,
+        InstrType::Altitude(AltitudeDir::Up),
+        InstrType::Altitude(AltitudeDir::Down),
+    ));
+    ins
+}
+
+/// Gets the instruction moving the ship by `disp` using waypoint `wp`, if `disp` is a
+/// whole multiple of `wp`.
+fn waypoint_move(disp: (i32, i32, i32), wp: (i32, i32, i32)) -> Option<Instruction> {
+    let (d, w) = ([disp.0, disp.1, disp.2], [wp.0, wp.1, wp.2]);
+    let axis = w.iter().position(|v| *v != 0)?;
+    if d[axis] % w[axis] != 0 {
+        return None;
+    }
+    let k = d[axis] / w[axis];
+    if (0..3).any(|a| d[a] != k * w[a]) {
+        return None;
+    }
+    axis_move(k, InstrType::Forward, InstrType::Backward)
+}
+
+/// Collapses a route navigated with a waypoint into a short equivalent route, which
+/// ends with the ship and waypoint at the same positions. The ship moves with a single
+/// forward or backward instruction, using either the starting waypoint, the final
+/// waypoint, or a waypoint teleported to the ship's destination.
+pub fn compact_waypoint(ins: &[Instruction], config: &NavConfig) -> Vec<Instruction> {
+    let start = WaypointState::new(config);
+    let mut end = start;
+    for i in ins.iter() {
+        end.apply(i);
+    }
+    let adjust = waypoint_change(start.wp, end.wp);
+    if end.ship == start.ship {
+        return adjust;
+    }
+
+    let mut candidates = Vec::new();
+    if let Some(mv) = waypoint_move(end.ship, start.wp) {
+        let mut route = vec![mv];
+        route.extend(adjust.iter().cloned());
+        candidates.push(route);
+    }
+    if let Some(mv) = waypoint_move(end.ship, end.wp) {
+        let mut route = adjust.clone();
+        route.push(mv);
+        candidates.push(route);
+    }
+    let mut route = waypoint_change(start.wp, end.ship);
+    route.push(Instruction::new(InstrType::Forward, 1));
+    route.extend(waypoint_change(end.ship, end.wp));
+    candidates.push(route);
+
+    candidates.into_iter().min_by_key(|r| r.len()).unwrap()
+}
@@ -0,0 +1,124 @@
+//! Provides recording of ship routes and exporting them as SVG images.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Space left around the route in SVG images, as a fraction of the route's extent.
+const SVG_MARGIN: f64 = 0.05;
+/// Width of SVG images in pixels. The height follows the route's aspect ratio.
+const SVG_WIDTH: f64 = 800.0;
+
+/// The positions of a ship, and optionally its waypoint, after every instruction, as
+/// seen from above.
+pub struct Trajectory {
+    /// Ship positions, starting with the initial position.
+    pub ship: Vec<(f64, f64)>,
+    /// Absolute waypoint positions, starting with the initial position. Empty when
+    /// navigating without a waypoint.
+    pub waypoint: Vec<(f64, f64)>,
+}
+
+impl Trajectory {
+    /// Creates an empty trajectory.
+    pub fn new() -> Self {
+        Self {
+            ship: Vec::new(),
+            waypoint: Vec::new(),
+        }
+    }
+
+    /// Records the ship's position.
+    pub fn record_ship(&mut self, x: f64, y: f64) {
+        self.ship.push((x, y));
+    }
+
+    /// Records the waypoint's absolute position.
+    pub fn record_waypoint(&mut self, x: f64, y: f64) {
+        self.waypoint.push((x, y));
+    }
+
+    /// Gets the minimum and maximum corners of a box containing every position.
+    fn bounds(&self) -> ((f64, f64), (f64, f64)) {
+        let mut min = (0.0f64, 0.0f64);
+        let mut max = (0.0f64, 0.0f64);
+        for (x, y) in self.ship.iter().chain(self.waypoint.iter()) {
+            min = (min.0.min(*x), min.1.min(*y));
+            max = (max.0.max(*x), max.1.max(*y));
+        }
+        (min, max)
+    }
+}
+
+/// Converts a y-coordinate to SVG coordinates, where y increases downwards.
+fn svg_y(y: f64) -> f64 {
+    // Subtracting from zero avoids printing "-0".
+    0.0 - y
+}
+
+/// Formats points as an SVG `points` attribute.
+fn svg_points(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .map(|(x, y)| format!("{},{}", x, svg_y(*y)))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Writes a trajectory as an SVG image with polylines for the ship's and waypoint's
+/// paths, and markers for the ship's start and end.
+///
+/// # Arguments
+///
+///  * `path` - Path of the SVG file to write.
+///  * `traj` - Trajectory to draw.
+pub fn write_svg<P>(path: P, traj: &Trajectory) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let ((min_x, min_y), (max_x, max_y)) = traj.bounds();
+    let extent = (max_x - min_x).max(max_y - min_y).max(1.0);
+    let margin = extent * SVG_MARGIN;
+    let (view_x, view_y) = (min_x - margin, svg_y(max_y) - margin);
+    let (view_w, view_h) = (max_x - min_x + 2.0 * margin, max_y - min_y + 2.0 * margin);
+    let marker_r = extent * 0.01;
+
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+        SVG_WIDTH,
+        (SVG_WIDTH * view_h / view_w).round(),
+        view_x,
+        view_y,
+        view_w,
+        view_h
+    )?;
+    if !traj.waypoint.is_empty() {
+        writeln!(
+            out,
+            r#"  <polyline points="{}" fill="none" stroke="orange" stroke-width="1" stroke-dasharray="4 2" vector-effect="non-scaling-stroke"/>"#,
+            svg_points(&traj.waypoint)
+        )?;
+    }
+    writeln!(
+        out,
+        r#"  <polyline points="{}" fill="none" stroke="steelblue" stroke-width="2" vector-effect="non-scaling-stroke"/>"#,
+        svg_points(&traj.ship)
+    )?;
+    let markers = [(traj.ship.first(), "green"), (traj.ship.last(), "red")];
+    for (point, color) in markers.iter() {
+        if let Some((x, y)) = point {
+            writeln!(
+                out,
+                r#"  <circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                x,
+                svg_y(*y),
+                marker_r,
+                color
+            )?;
+        }
+    }
+    writeln!(out, "</svg>")?;
+    out.flush()
+}
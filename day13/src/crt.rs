@@ -0,0 +1,134 @@
+//! Provides a solver for systems of congruences using the Chinese Remainder Theorem.
+//!
+//! Moduli do not need to be pairwise coprime. All arithmetic is done on `u128` and
+//! checked, so large systems report an overflow instead of silently wrapping.
+
+/// A congruence `x ≡ residue (mod modulus)`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Congruence {
+    pub residue: u128,
+    pub modulus: u128,
+}
+
+impl Congruence {
+    /// Creates a congruence, reducing `residue` modulo `modulus`. `modulus` must be
+    /// positive.
+    pub fn new(residue: u128, modulus: u128) -> Self {
+        Self {
+            residue: residue % modulus,
+            modulus,
+        }
+    }
+}
+
+/// Adds `a` and `b` modulo `m`, where `a` and `b` are already reduced.
+fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
+/// Subtracts `b` from `a` modulo `m`, where `a` and `b` are already reduced.
+fn sub_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= b {
+        a - b
+    } else {
+        m - (b - a)
+    }
+}
+
+/// Multiplies `a` and `b` modulo `m`, where `a` and `b` are already reduced. Falls back
+/// on double-and-add when the product does not fit in a `u128`.
+fn mul_mod(a: u128, b: u128, m: u128) -> u128 {
+    if let Some(p) = a.checked_mul(b) {
+        return p % m;
+    }
+    let (mut a, mut b) = (a, b);
+    let mut product = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            product = add_mod(product, a, m);
+        }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    product
+}
+
+/// Runs the extended Euclidean algorithm on `a` and `m`.
+///
+/// # Returns
+///
+/// The greatest common divisor `g` of `a` and `m`, and a coefficient `s` such that
+/// `a * s ≡ g (mod m)`.
+fn ext_gcd(a: u128, m: u128) -> (u128, u128) {
+    // Invariants: old_r ≡ a * old_s and r ≡ a * s (mod m).
+    let (mut old_r, mut r) = (a % m, m);
+    let (mut old_s, mut s) = (1 % m, 0);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, sub_mod(old_s, mul_mod(q % m, s, m), m));
+    }
+    (old_r, old_s)
+}
+
+/// Merges two congruences into a single congruence satisfied by exactly the same
+/// values.
+///
+/// # Returns
+///
+/// The merged congruence, `Ok(None)` if no value satisfies both, or an error if the
+/// merged modulus does not fit in a `u128`.
+fn merge(a: Congruence, b: Congruence) -> Result<Option<Congruence>, String> {
+    let (g, s) = ext_gcd(a.modulus, b.modulus);
+    let diff = sub_mod(b.residue % b.modulus, a.residue % b.modulus, b.modulus);
+    if !diff.is_multiple_of(g) {
+        return Ok(None);
+    }
+    // a.modulus / g is invertible modulo b.modulus / g, with inverse s.
+    let reduced = b.modulus / g;
+    let k = mul_mod((diff / g) % reduced, s % reduced, reduced);
+    let modulus = match a.modulus.checked_mul(reduced) {
+        Some(m) => m,
+        None => return Err("Combined modulus overflows 128 bits".into()),
+    };
+    // k < reduced, so neither of these can exceed the combined modulus.
+    Ok(Some(Congruence {
+        residue: a.residue + a.modulus * k,
+        modulus,
+    }))
+}
+
+/// Solves a system of congruences.
+///
+/// # Arguments
+///
+///  * `congruences` - Congruences that must all hold. Moduli must be positive.
+///
+/// # Returns
+///
+/// A congruence whose residue is the smallest non-negative solution, and whose modulus
+/// is the least common multiple of all moduli. Every solution is the residue plus a
+/// multiple of the modulus. Returns an error if there is no solution or if the
+/// arithmetic overflows.
+pub fn solve(congruences: &[Congruence]) -> Result<Congruence, String> {
+    let mut solution = Congruence::new(0, 1);
+    for (i, c) in congruences.iter().enumerate() {
+        if c.modulus == 0 {
+            return Err(format!("Congruence {} has a modulus of zero", i + 1));
+        }
+        solution = match merge(solution, *c)? {
+            Some(s) => s,
+            None => {
+                return Err(format!(
+                    "No solution: x ≡ {} (mod {}) conflicts with the preceding congruences",
+                    c.residue, c.modulus
+                ))
+            }
+        };
+    }
+    Ok(solution)
+}
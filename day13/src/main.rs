@@ -1,19 +1,83 @@
 #[macro_use]
 extern crate utils;
 
+mod crt;
+mod schedule;
+mod timeline;
+
+use crt::Congruence;
+use schedule::BusConstraint;
 use std::env;
 use std::path::Path;
 use std::process;
-use std::usize;
 use utils::read_lines;
 
+/// Command line options.
+struct Options {
+    /// Path to the input file.
+    input_path: String,
+    /// Number of times to list at which all schedule constraints hold.
+    times: usize,
+    /// Number of minutes either side of the earliest solution time to print a timetable
+    /// for.
+    timeline: Option<u128>,
+    /// Number of upcoming departures to list per bus.
+    next: Option<usize>,
+    /// Time to list upcoming departures after, instead of the input's departure time.
+    after: Option<u128>,
+}
+
 /// Prints usage statement for the executable.
 fn usage(args: Vec<String>) {
-    println!("Usage: {} input_file scope", args[0]);
+    println!(
+        "Usage: {} input_file [--times N] [--timeline RADIUS] [--next K] [--after T]",
+        args[0]
+    );
+    println!();
+    println!("Bus entries are \"x\", \"ID\" to depart at the entry's position, or");
+    println!("\"ID@OFFSET\" to depart at an explicit offset.");
 }
 
-/// Reads departure time and bus IDs from a file.
-fn read_bus_data<P>(filename: P) -> Result<(u32, Vec<Option<u32>>), String>
+/// Parses command line arguments.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let input_path = match args.get(1) {
+        Some(p) => p.clone(),
+        None => return Err("Missing input file".into()),
+    };
+    let mut opts = Options {
+        input_path,
+        times: 1,
+        timeline: None,
+        next: None,
+        after: None,
+    };
+    let mut iter = args.iter().skip(2);
+    while let Some(flag) = iter.next() {
+        match flag.as_str() {
+            "--times" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => opts.times = n,
+                None => return Err("Expected a number for --times".into()),
+            },
+            "--timeline" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => opts.timeline = Some(n),
+                None => return Err("Expected a number of minutes for --timeline".into()),
+            },
+            "--next" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => opts.next = Some(n),
+                None => return Err("Expected a number for --next".into()),
+            },
+            "--after" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => opts.after = Some(n),
+                None => return Err("Expected a time for --after".into()),
+            },
+            _ => return Err(format!("Unexpected argument: {}", flag)),
+        }
+    }
+    Ok(opts)
+}
+
+/// Reads departure time and bus constraints from a file.
+fn read_bus_data<P>(filename: P) -> Result<(u32, Vec<BusConstraint>), String>
 where
     P: AsRef<Path>,
 {
@@ -37,7 +101,7 @@ where
         lines_str.push(line);
     }
     if lines_str.len() < 2 {
-        return Err(format!("Not enough lines"));
+        return Err("Not enough lines".into());
     }
     let depart = match lines_str[0].parse() {
         Ok(n) => n,
@@ -48,81 +112,68 @@ where
             ))
         }
     };
-    let mut bus_ids = Vec::new();
-    for id_str in lines_str[1].split(',') {
-        if id_str == "x" {
-            bus_ids.push(None);
-        } else {
-            match id_str.parse::<u32>() {
-                Ok(id) => bus_ids.push(Some(id)),
-                Err(_) => return Err(format!("Failed to parse bus ID \"{}\"", id_str)),
-            }
-        }
-    }
-    Ok((depart, bus_ids))
+    let buses = schedule::parse_buses(&lines_str[1])?;
+    Ok((depart, buses))
 }
 
 /// Calculates the time until a bus arrives after a given `start` time.
-fn get_time_until_bus(start: u32, bus_id: u32) -> usize {
+pub fn get_time_until_bus(start: u128, bus_id: u32) -> usize {
+    let bus_id = bus_id as u128;
     // Two modulos are use to avoid a subtract with underflow.
     ((bus_id - start % bus_id) % bus_id) as usize
 }
 
 /// Gets the bus with the shortest time until arrival after a given `start` time.
 /// Returns the bus ID and minimum wait time.
-fn get_earliest_bus(start: u32, bus_ids: &Vec<Option<u32>>) -> Option<(u32, usize)> {
+fn get_earliest_bus(start: u32, buses: &[BusConstraint]) -> Option<(u32, usize)> {
     let mut best_bus_id: Option<u32> = None;
     let mut min_wait_time: usize = usize::MAX;
-    for bus_id in bus_ids.iter() {
-        if let Some(id) = bus_id.as_ref() {
-            let wait_time = get_time_until_bus(start, *id);
-            if wait_time < min_wait_time {
-                best_bus_id = Some(*id);
-                min_wait_time = wait_time;
-            }
+    for bus in buses.iter() {
+        let wait_time = get_time_until_bus(start as u128, bus.id);
+        if wait_time < min_wait_time {
+            best_bus_id = Some(bus.id);
+            min_wait_time = wait_time;
         }
     }
     best_bus_id.map(|id| (id, min_wait_time))
 }
 
-/// Find the lowest time at which all buses leave in order a minute after the last.
-fn get_lowest_subsequent_depart_time(bus_ids: &Vec<Option<u32>>) -> usize {
-    let mut step = 1;
-    let mut t: usize = 0;
-    for cur_id in bus_ids.iter() {
-        if let Some(bus_incr) = cur_id.map(|id| id as usize) {
-            loop {
-                if t % bus_incr == 0 {
-                    step *= bus_incr;
-                    break;
-                } else {
-                    t += step;
-                }
-            }
+/// Prints the first `times` times at which all constraints in a solved schedule hold.
+fn print_schedule(solution: &Congruence, times: usize) {
+    println!(
+        "Soonest time for synchronous subsequent departures: {}",
+        solution.residue
+    );
+    if times > 1 {
+        println!("Constraints repeat every {} minutes", solution.modulus);
+        for (i, t) in schedule::depart_times(solution, times).iter().enumerate() {
+            println!("Time {}: {}", i + 1, t);
         }
-        t += 1;
     }
-    t - bus_ids.len()
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        usage(args);
-        process::exit(1);
-    }
-    let input_path = String::from(&args[1]);
-    let (depart, bus_ids) = timed_section!("Initialization", { read_bus_data(&input_path) }, |v| {
-        match v {
-            Ok((d, bids)) => (d, bids),
-            Err(e) => {
-                eprintln!("Failed to read bus data: {}", e);
-                process::exit(1);
-            }
+    let opts = match parse_args(&args) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}", e);
+            usage(args);
+            process::exit(1);
         }
-    });
+    };
+    let (depart, buses) =
+        timed_section!("Initialization", { read_bus_data(&opts.input_path) }, |v| {
+            match v {
+                Ok((d, b)) => (d, b),
+                Err(e) => {
+                    eprintln!("Failed to read bus data: {}", e);
+                    process::exit(1);
+                }
+            }
+        });
 
-    timed_section!("Part 1", { get_earliest_bus(depart, &bus_ids) }, |v| {
+    timed_section!("Part 1", { get_earliest_bus(depart, &buses) }, |v| {
         match v {
             Some((earliest_bus, wait_time)) => {
                 println!("Earliest available bus: {}", earliest_bus);
@@ -138,14 +189,31 @@ fn main() {
         }
     });
 
-    timed_section!(
-        "Part 2",
-        { get_lowest_subsequent_depart_time(&bus_ids) },
-        |soonest_seq_time| {
-            println!(
-                "Soonest time for synchronous subsequent departures: {}",
-                soonest_seq_time
-            );
+    let solution = timed_section!("Part 2", { schedule::solve(&buses) }, |v| {
+        match v {
+            Ok(solution) => {
+                print_schedule(&solution, opts.times);
+                Some(solution)
+            }
+            Err(e) => {
+                println!("No synchronous subsequent departures: {}", e);
+                None
+            }
         }
-    );
+    });
+
+    if let (Some(radius), Some(solution)) = (opts.timeline, solution) {
+        println!("==== Timetable ====");
+        timeline::print_timetable(
+            &buses,
+            solution.residue.saturating_sub(radius),
+            solution.residue.saturating_add(radius),
+        );
+        println!();
+    }
+    if let Some(count) = opts.next {
+        let after = opts.after.unwrap_or(depart as u128);
+        println!("==== Next departures after {} ====", after);
+        timeline::print_next_departures(&buses, after, count);
+    }
 }
@@ -0,0 +1,78 @@
+//! Provides bus schedule constraints and solving for times that satisfy them.
+
+use crate::crt::{self, Congruence};
+
+/// Requires that bus `id` departs `offset` minutes after a time `t`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BusConstraint {
+    pub id: u32,
+    pub offset: i64,
+}
+
+/// Parses a comma-separated list of bus constraints.
+///
+/// Each entry is one of:
+///
+///  * `x` - No constraint.
+///  * `ID` - Bus `ID` departs at an offset equal to the entry's position in the list.
+///  * `ID@OFFSET` - Bus `ID` departs at an explicit `OFFSET`, which may be negative or
+///    beyond the end of the list.
+///
+/// # Returns
+///
+/// The constraints, in list order.
+pub fn parse_buses(line: &str) -> Result<Vec<BusConstraint>, String> {
+    let mut buses = Vec::new();
+    for (i, entry) in line.split(',').enumerate() {
+        let entry = entry.trim();
+        if entry == "x" {
+            continue;
+        }
+        let (id_str, offset) = match entry.split_once('@') {
+            Some((id_str, offset_str)) => match offset_str.parse::<i64>() {
+                Ok(offset) => (id_str, offset),
+                Err(_) => return Err(format!("Failed to parse offset \"{}\"", offset_str)),
+            },
+            None => (entry, i as i64),
+        };
+        match id_str.parse::<u32>() {
+            Ok(0) => return Err("Bus IDs must be positive".into()),
+            Ok(id) => buses.push(BusConstraint { id, offset }),
+            Err(_) => return Err(format!("Failed to parse bus ID \"{}\"", id_str)),
+        }
+    }
+    Ok(buses)
+}
+
+/// Finds every time at which all constraints hold.
+///
+/// # Returns
+///
+/// A congruence whose residue is the earliest such time and whose modulus is the
+/// period between such times, or an error if no such time exists.
+pub fn solve(buses: &[BusConstraint]) -> Result<Congruence, String> {
+    // Bus `id` at offset `o` must leave at a time `t` where `t + o ≡ 0 (mod id)`.
+    let congruences: Vec<Congruence> = buses
+        .iter()
+        .map(|bus| {
+            let id = bus.id as i128;
+            Congruence::new((-(bus.offset as i128)).rem_euclid(id) as u128, id as u128)
+        })
+        .collect();
+    crt::solve(&congruences)
+}
+
+/// Lists the first `n` times at which all constraints in a solved schedule hold. Fewer
+/// times are returned if later times would overflow.
+pub fn depart_times(schedule: &Congruence, n: usize) -> Vec<u128> {
+    let mut times = Vec::new();
+    let mut t = Some(schedule.residue);
+    while let Some(cur) = t {
+        if times.len() == n {
+            break;
+        }
+        times.push(cur);
+        t = cur.checked_add(schedule.modulus);
+    }
+    times
+}
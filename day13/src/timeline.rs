@@ -0,0 +1,64 @@
+//! Provides printing of bus departure timetables.
+
+use crate::get_time_until_bus;
+use crate::schedule::BusConstraint;
+
+/// Prints a grid of which buses depart at each minute from `start` to `end` inclusive,
+/// marking departures with `D` and other minutes with `.`.
+pub fn print_timetable(buses: &[BusConstraint], start: u128, end: u128) {
+    let headers: Vec<String> = buses.iter().map(|b| format!("bus {}", b.id)).collect();
+    let time_width = end.to_string().len().max("time".len());
+    print!("{:<width$}", "time", width = time_width);
+    for header in headers.iter() {
+        print!("  {}", header);
+    }
+    println!();
+
+    for t in start..=end {
+        let mut row = format!("{:<width$}", t, width = time_width);
+        for (bus, header) in buses.iter().zip(headers.iter()) {
+            let mark = if get_time_until_bus(t, bus.id) == 0 {
+                'D'
+            } else {
+                '.'
+            };
+            // Center the mark under its header.
+            let left = (header.len() - 1) / 2;
+            row.push_str(&format!(
+                "  {:left$}{}{:right$}",
+                "",
+                mark,
+                "",
+                left = left,
+                right = header.len() - 1 - left
+            ));
+        }
+        println!("{}", row.trim_end());
+    }
+}
+
+/// Lists the next `count` departures of a bus at or after time `start`. Fewer times
+/// are returned if later times would overflow.
+pub fn next_departures(start: u128, bus_id: u32, count: usize) -> Vec<u128> {
+    let mut times = Vec::new();
+    let mut t = start.checked_add(get_time_until_bus(start, bus_id) as u128);
+    while let Some(cur) = t {
+        if times.len() == count {
+            break;
+        }
+        times.push(cur);
+        t = cur.checked_add(bus_id as u128);
+    }
+    times
+}
+
+/// Prints the next `count` departures of each bus at or after time `start`.
+pub fn print_next_departures(buses: &[BusConstraint], start: u128, count: usize) {
+    for bus in buses.iter() {
+        let times: Vec<String> = next_departures(start, bus.id, count)
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        println!("Bus {}: {}", bus.id, times.join(", "));
+    }
+}
@@ -1,26 +1,90 @@
 #[macro_use]
 extern crate utils;
 
+mod symbolic;
+mod trace;
+
 use std::collections::HashMap;
 use std::env;
 use std::path::Path;
 use std::process;
-use std::usize;
 use utils::read_lines;
 
+/// Largest supported word width, in bits.
+const MAX_WIDTH: usize = 128;
+
+/// Word width used when a program has no masks, in bits.
+const DEFAULT_WIDTH: usize = 36;
+
 /// Single line of instruction.
-enum MaskInstr {
-    Mask(usize, usize, usize),
-    Mem(usize, usize),
+pub enum MaskInstr {
+    Mask(u128, u128, u128),
+    Mem(u128, u128),
+}
+
+/// Mask instructions along with the word width they operate on.
+pub struct Program {
+    /// Word width in bits, taken from the length of the masks.
+    pub width: usize,
+    pub ins: Vec<MaskInstr>,
+}
+
+/// Gets a mask with the lowest `width` bits set.
+pub fn word_mask(width: usize) -> u128 {
+    if width >= MAX_WIDTH {
+        u128::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+/// Command line options.
+struct Options {
+    /// Path to the input file.
+    input_path: String,
+    /// Whether method 2 stores floating addresses symbolically instead of enumerating
+    /// them.
+    symbolic: bool,
+    /// Whether to log each instruction as it executes.
+    trace: bool,
+    /// Whether to print final memory.
+    dump: bool,
 }
 
 /// Prints usage statement for the executable.
 fn usage(args: Vec<String>) {
-    println!("Usage: {} input_file scope", args[0]);
+    println!(
+        "Usage: {} input_file [--symbolic] [--trace] [--dump]",
+        args[0]
+    );
 }
 
-/// Reads mask instructions from a file.
-fn read_mask_ins<P>(filename: P) -> Result<Vec<MaskInstr>, String>
+/// Parses command line arguments.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let input_path = match args.get(1) {
+        Some(p) => p.clone(),
+        None => return Err("Missing input file".into()),
+    };
+    let mut opts = Options {
+        input_path,
+        symbolic: false,
+        trace: false,
+        dump: false,
+    };
+    for flag in args.iter().skip(2) {
+        match flag.as_str() {
+            "--symbolic" => opts.symbolic = true,
+            "--trace" => opts.trace = true,
+            "--dump" => opts.dump = true,
+            _ => return Err(format!("Unexpected argument: {}", flag)),
+        }
+    }
+    Ok(opts)
+}
+
+/// Reads mask instructions from a file. The word width is the length of the masks,
+/// which must all be the same length and at most 128 bits.
+fn read_mask_ins<P>(filename: P) -> Result<Program, String>
 where
     P: AsRef<Path>,
 {
@@ -33,6 +97,9 @@ where
     };
 
     let mut ins: Vec<MaskInstr> = Vec::new();
+    let mut width: Option<usize> = None;
+    // Line numbers of memory writes, for checking against the word width afterwards.
+    let mut mem_lines: Vec<usize> = Vec::new();
     for (i, line_res) in lines.enumerate() {
         let line = match line_res {
             Ok(l) => l,
@@ -46,11 +113,30 @@ where
             return Err(format!("Bad line {}", i + 1));
         }
         if tokens[0] == "mask" {
-            let mut zeros_mask: usize = 0;
-            let mut ones_mask: usize = 0;
-            let mut x_mask: usize = 0;
-            for (i, ch) in tokens[2].chars().rev().enumerate() {
-                let bit: usize = 1 << i;
+            let mask_width = tokens[2].chars().count();
+            if mask_width > MAX_WIDTH {
+                return Err(format!(
+                    "Mask on line {} is wider than {} bits",
+                    i + 1,
+                    MAX_WIDTH
+                ));
+            }
+            match width {
+                Some(w) if w != mask_width => {
+                    return Err(format!(
+                        "Mask on line {} is {} bits wide, expected {} bits",
+                        i + 1,
+                        mask_width,
+                        w
+                    ))
+                }
+                _ => width = Some(mask_width),
+            }
+            let mut zeros_mask: u128 = 0;
+            let mut ones_mask: u128 = 0;
+            let mut x_mask: u128 = 0;
+            for (bit_i, ch) in tokens[2].chars().rev().enumerate() {
+                let bit: u128 = 1 << bit_i;
                 match ch {
                     '0' => zeros_mask |= bit,
                     '1' => ones_mask |= bit,
@@ -65,72 +151,112 @@ where
                 }
             }
             ins.push(MaskInstr::Mask(zeros_mask, ones_mask, x_mask));
-        } else if &tokens[0][..3] == "mem" {
+        } else if tokens[0].starts_with("mem[") && tokens[0].ends_with(']') {
             let index_str = &tokens[0][4..tokens[0].len() - 1];
-            let addr: usize = match index_str.parse() {
+            let addr: u128 = match index_str.parse() {
                 Ok(i) => i,
                 Err(_) => return Err(format!("Bad index \"{}\" on line {}", index_str, i + 1)),
             };
-            let val: usize = match tokens[2].parse() {
+            let val: u128 = match tokens[2].parse() {
                 Ok(i) => i,
                 Err(_) => return Err(format!("Bad value on line {}", i + 1)),
             };
             ins.push(MaskInstr::Mem(addr, val));
+            mem_lines.push(i + 1);
         } else {
             return Err(format!("Bad line {}", i + 1));
         }
     }
-    Ok(ins)
+
+    let width = width.unwrap_or(DEFAULT_WIDTH);
+    let limit = word_mask(width);
+    let mems = ins.iter().filter_map(|i| match i {
+        MaskInstr::Mem(addr, val) => Some((addr, val)),
+        _ => None,
+    });
+    for ((addr, val), line_num) in mems.zip(mem_lines.iter()) {
+        if *addr > limit {
+            return Err(format!(
+                "Address {} on line {} does not fit in {} bits",
+                addr, line_num, width
+            ));
+        }
+        if *val > limit {
+            return Err(format!(
+                "Value {} on line {} does not fit in {} bits",
+                val, line_num, width
+            ));
+        }
+    }
+    Ok(Program { width, ins })
 }
 
-/// Use method 1 to read the mask instructions. Returns the sum of all values left in memory.
-fn exec_ins_method1(ins: &Vec<MaskInstr>) -> usize {
-    let mut and_mask = usize::MAX;
-    let mut or_mask: usize = 0;
-    let mut values: HashMap<usize, usize> = HashMap::new();
-    for i in ins {
+/// Sums the values left in memory.
+fn sum_memory(values: &HashMap<u128, u128>) -> Result<u128, String> {
+    values.values().try_fold(0u128, |sum, v| {
+        sum.checked_add(*v)
+            .ok_or_else(|| "Sum of memory overflows 128 bits".to_string())
+    })
+}
+
+/// Use method 1 to read the mask instructions, logging each instruction if `trace` is
+/// set. Returns the values left in memory.
+fn exec_ins_method1(prog: &Program, trace: bool) -> HashMap<u128, u128> {
+    let mut and_mask = word_mask(prog.width);
+    let mut or_mask: u128 = 0;
+    let mut values: HashMap<u128, u128> = HashMap::new();
+    for i in prog.ins.iter() {
         match i {
-            MaskInstr::Mask(zeros, ones, _) => {
-                and_mask = !zeros;
+            MaskInstr::Mask(zeros, ones, float) => {
+                if trace {
+                    trace::trace_mask(*zeros, *ones, *float, prog.width);
+                }
+                and_mask = !zeros & word_mask(prog.width);
                 or_mask = *ones;
             }
             MaskInstr::Mem(addr, v) => {
-                values.insert(*addr, (v & and_mask) | or_mask);
+                let new = (v & and_mask) | or_mask;
+                let old = values.insert(*addr, new);
+                if trace {
+                    trace::trace_mem(*addr, *v);
+                    trace::trace_write(*addr, old, new, prog.width);
+                }
             }
         }
     }
-    values.iter().fold(0, |sum, (_, v)| sum + v)
+    values
 }
 
-/// Use method 2 to read the mask instructions. Returns the sum of all values left in memory.
-fn exec_ins_method2(ins: &Vec<MaskInstr>) -> usize {
-    let mut unchanged_mask = usize::MAX;
-    let mut change_mask: usize = 0;
-    let mut float_mask: usize = 0;
-    let mut values: HashMap<usize, usize> = HashMap::new();
-    for i in ins {
+/// Use method 2 to read the mask instructions, logging each instruction if `trace` is
+/// set. Returns the values left in memory.
+fn exec_ins_method2(prog: &Program, trace: bool) -> Result<HashMap<u128, u128>, String> {
+    let mut unchanged_mask = word_mask(prog.width);
+    let mut change_mask: u128 = 0;
+    let mut float_mask: u128 = 0;
+    let mut values: HashMap<u128, u128> = HashMap::new();
+    for i in prog.ins.iter() {
         match i {
             MaskInstr::Mask(zeros, ones, float) => {
+                if trace {
+                    trace::trace_mask(*zeros, *ones, *float, prog.width);
+                }
                 unchanged_mask = *zeros;
                 change_mask = *ones;
                 float_mask = *float;
             }
             MaskInstr::Mem(addr, v) => {
-                let base_addr: usize = (addr & unchanged_mask) | change_mask;
-                let mut num_combos: usize = 0;
-                for i in 0..36 {
-                    if (float_mask >> i) & 1 == 1 {
-                        if num_combos == 0 {
-                            num_combos = 2;
-                        } else {
-                            num_combos *= 2;
-                        }
-                    }
+                if trace {
+                    trace::trace_mem(*addr, *v);
                 }
+                let base_addr: u128 = (addr & unchanged_mask) | change_mask;
+                let num_combos = match 1u128.checked_shl(float_mask.count_ones()) {
+                    Some(n) => n,
+                    None => return Err("Too many floating bits to enumerate".into()),
+                };
                 for combo in 0..num_combos {
                     let mut new_addr = base_addr;
                     let mut one_i = 0;
-                    for i in 0..36 {
+                    for i in 0..prog.width {
                         if (float_mask >> i) & 1 == 1 {
                             if combo >> one_i & 1 == 1 {
                                 new_addr |= 1 << i;
@@ -138,24 +264,42 @@ fn exec_ins_method2(ins: &Vec<MaskInstr>) -> usize {
                             one_i += 1;
                         }
                     }
-                    values.insert(new_addr, *v);
+                    let old = values.insert(new_addr, *v);
+                    if trace {
+                        trace::trace_write(new_addr, old, *v, prog.width);
+                    }
                 }
             }
         }
     }
-    values.iter().fold(0, |sum, (_, v)| sum + v)
+    Ok(values)
+}
+
+/// Prints the sum of memory for a part, printing memory itself first if `dump` is set.
+fn report_memory(part: &str, values: &HashMap<u128, u128>, width: usize, dump: bool) {
+    if dump {
+        println!("Final memory:");
+        trace::dump_memory(values, width);
+    }
+    match sum_memory(values) {
+        Ok(sum) => println!("{}: {}", part, sum),
+        Err(e) => println!("{} failed: {}", part, e),
+    }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        usage(args);
-        process::exit(1);
-    }
-    let input_path = String::from(&args[1]);
-    let ins = timed_section!("Initialization", { read_mask_ins(&input_path) }, |v| {
+    let opts = match parse_args(&args) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}", e);
+            usage(args);
+            process::exit(1);
+        }
+    };
+    let prog = timed_section!("Initialization", { read_mask_ins(&opts.input_path) }, |v| {
         match v {
-            Ok(ins) => ins,
+            Ok(prog) => prog,
             Err(e) => {
                 eprintln!("Failed to read mask instruction data: {}", e);
                 process::exit(1);
@@ -163,11 +307,37 @@ fn main() {
         }
     });
 
-    timed_section!("Part 1", { exec_ins_method1(&ins) }, |v| {
-        println!("part 1: {}", v);
-    });
+    println!("Word width: {} bits\n", prog.width);
 
-    timed_section!("Part 2", { exec_ins_method2(&ins) }, |v| {
-        println!("part 2: {}", v);
-    });
+    timed_section!(
+        "Part 1",
+        { exec_ins_method1(&prog, opts.trace) },
+        |values| {
+            report_memory("part 1", &values, prog.width, opts.dump);
+        }
+    );
+
+    if opts.symbolic {
+        timed_section!(
+            "Part 2",
+            { symbolic::exec_ins_method2_symbolic(&prog, opts.trace) },
+            |memory: symbolic::SymbolicMemory| {
+                if opts.dump {
+                    println!("Final memory:");
+                    memory.dump();
+                }
+                match memory.sum() {
+                    Ok(sum) => println!("part 2: {}", sum),
+                    Err(e) => println!("part 2 failed: {}", e),
+                }
+            }
+        );
+    } else {
+        timed_section!("Part 2", { exec_ins_method2(&prog, opts.trace) }, |v| {
+            match v {
+                Ok(values) => report_memory("part 2", &values, prog.width, opts.dump),
+                Err(e) => println!("part 2 failed: {}", e),
+            }
+        });
+    }
 }
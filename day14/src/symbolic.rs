@@ -0,0 +1,176 @@
+//! Provides a memory model for method 2 that stores floating addresses symbolically.
+//!
+//! Each write covers a ternary address pattern instead of every address it decodes to.
+//! Older writes are trimmed so that stored patterns never overlap, which lets the sum of
+//! memory be computed without enumerating addresses.
+
+use crate::trace;
+use crate::{word_mask, MaskInstr, Program};
+
+/// A set of addresses where the bits in `fixed` match `bits` and every other bit takes
+/// both values.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct AddressPattern {
+    fixed: u128,
+    bits: u128,
+}
+
+impl AddressPattern {
+    /// Checks whether two patterns share any address.
+    fn overlaps(&self, other: &AddressPattern) -> bool {
+        let both = self.fixed & other.fixed;
+        self.bits & both == other.bits & both
+    }
+
+    /// Gets the addresses shared with `other`, which must overlap this pattern.
+    fn intersect(&self, other: &AddressPattern) -> AddressPattern {
+        AddressPattern {
+            fixed: self.fixed | other.fixed,
+            bits: self.bits | other.bits,
+        }
+    }
+
+    /// Splits off the addresses in this pattern that are not in `other`.
+    ///
+    /// # Returns
+    ///
+    /// Non-overlapping patterns covering exactly the addresses in `self` but not in
+    /// `other`.
+    fn subtract(&self, other: &AddressPattern) -> Vec<AddressPattern> {
+        if !self.overlaps(other) {
+            return vec![*self];
+        }
+        let mut pieces = Vec::new();
+        let mut rest = *self;
+        // Fix each bit that `other` fixes but `self` does not. Choosing the opposite
+        // value to `other` gives a piece outside `other`.
+        let mut split_bits = other.fixed & !self.fixed;
+        while split_bits != 0 {
+            let bit = split_bits & split_bits.wrapping_neg();
+            split_bits &= !bit;
+            pieces.push(AddressPattern {
+                fixed: rest.fixed | bit,
+                bits: rest.bits | (!other.bits & bit),
+            });
+            rest = AddressPattern {
+                fixed: rest.fixed | bit,
+                bits: rest.bits | (other.bits & bit),
+            };
+        }
+        // What remains lies entirely within `other`.
+        pieces
+    }
+
+    /// Counts the addresses in the pattern for a word of `width` bits.
+    fn count(&self, width: usize) -> Option<u128> {
+        let floating = (word_mask(width) & !self.fixed).count_ones();
+        1u128.checked_shl(floating)
+    }
+}
+
+/// Memory made of non-overlapping address patterns, each holding a single value.
+pub struct SymbolicMemory {
+    width: usize,
+    regions: Vec<(AddressPattern, u128)>,
+}
+
+impl SymbolicMemory {
+    /// Creates empty memory for a word of `width` bits.
+    fn new(width: usize) -> Self {
+        Self {
+            width,
+            regions: Vec::new(),
+        }
+    }
+
+    /// Writes `val` to every address in `pattern`.
+    ///
+    /// # Returns
+    ///
+    /// The part of each stored region that was overwritten, along with its old value.
+    fn write(&mut self, pattern: AddressPattern, val: u128) -> Vec<(AddressPattern, u128)> {
+        let mut regions = Vec::with_capacity(self.regions.len() + 1);
+        let mut overwritten = Vec::new();
+        for (region, region_val) in self.regions.iter() {
+            if region.overlaps(&pattern) {
+                overwritten.push((region.intersect(&pattern), *region_val));
+            }
+            for piece in region.subtract(&pattern) {
+                regions.push((piece, *region_val));
+            }
+        }
+        // Zeros add nothing to the sum, so they only need to erase older writes.
+        if val != 0 {
+            regions.push((pattern, val));
+        }
+        self.regions = regions;
+        overwritten
+    }
+
+    /// Prints every stored pattern and its value, sorted by pattern, with values in hex
+    /// and binary.
+    pub fn dump(&self) {
+        let mut regions: Vec<&(AddressPattern, u128)> = self.regions.iter().collect();
+        regions.sort_by_key(|(pattern, _)| (pattern.bits, pattern.fixed));
+        for (pattern, val) in regions {
+            println!(
+                "{} = {} {}",
+                trace::format_pattern(pattern.fixed, pattern.bits, self.width),
+                trace::format_hex(*val, self.width),
+                trace::format_bin(*val, self.width)
+            );
+        }
+    }
+
+    /// Sums the values at every address.
+    pub fn sum(&self) -> Result<u128, String> {
+        let overflow = || "Sum of memory overflows 128 bits".to_string();
+        self.regions.iter().try_fold(0u128, |sum, (pattern, val)| {
+            let count = pattern.count(self.width).ok_or_else(overflow)?;
+            let total = count.checked_mul(*val).ok_or_else(overflow)?;
+            sum.checked_add(total).ok_or_else(overflow)
+        })
+    }
+}
+
+/// Use method 2 to read the mask instructions, storing floating addresses symbolically
+/// and logging each instruction if `trace` is set. Returns the memory left afterwards.
+pub fn exec_ins_method2_symbolic(prog: &Program, trace: bool) -> SymbolicMemory {
+    let word = word_mask(prog.width);
+    let mut unchanged_mask = word;
+    let mut change_mask: u128 = 0;
+    let mut float_mask: u128 = 0;
+    let mut memory = SymbolicMemory::new(prog.width);
+    for i in prog.ins.iter() {
+        match i {
+            MaskInstr::Mask(zeros, ones, float) => {
+                if trace {
+                    trace::trace_mask(*zeros, *ones, *float, prog.width);
+                }
+                unchanged_mask = *zeros;
+                change_mask = *ones;
+                float_mask = *float;
+            }
+            MaskInstr::Mem(addr, v) => {
+                let fixed = word & !float_mask;
+                let pattern = AddressPattern {
+                    fixed,
+                    bits: ((addr & unchanged_mask) | change_mask) & fixed,
+                };
+                let overwritten = memory.write(pattern, *v);
+                if trace {
+                    trace::trace_mem(*addr, *v);
+                    println!(
+                        "  {}: {}",
+                        trace::format_pattern(pattern.fixed, pattern.bits, prog.width),
+                        v
+                    );
+                    for (region, old) in overwritten {
+                        trace::trace_overwrite(region.fixed, region.bits, old, *v, prog.width);
+                    }
+                }
+            }
+        }
+    }
+    memory
+}
@@ -0,0 +1,82 @@
+//! Provides tracing of executed instructions and dumping of memory, for debugging mask
+//! semantics.
+
+use std::collections::HashMap;
+
+/// Formats the lowest `width` bits of `v` in binary.
+pub fn format_bin(v: u128, width: usize) -> String {
+    format!("{:0w$b}", v, w = width)
+}
+
+/// Formats `v` in hex, padded to the number of digits in a word of `width` bits.
+pub fn format_hex(v: u128, width: usize) -> String {
+    format!("0x{:0w$x}", v, w = width.div_ceil(4))
+}
+
+/// Formats a ternary pattern of `width` bits, where bits in `fixed` are shown from
+/// `bits` and every other bit is shown as `X`.
+pub fn format_pattern(fixed: u128, bits: u128, width: usize) -> String {
+    (0..width)
+        .rev()
+        .map(|i| match ((fixed >> i) & 1, (bits >> i) & 1) {
+            (0, _) => 'X',
+            (_, 0) => '0',
+            _ => '1',
+        })
+        .collect()
+}
+
+/// Logs a mask instruction, decoded into the bits it sets to zero, one, and floating.
+pub fn trace_mask(zeros: u128, ones: u128, float: u128, width: usize) {
+    println!("mask = {}", format_pattern(!float, ones, width));
+    println!("  zero bits:  {}", format_bin(zeros, width));
+    println!("  one bits:   {}", format_bin(ones, width));
+    println!("  float bits: {}", format_bin(float, width));
+}
+
+/// Logs a memory instruction.
+pub fn trace_mem(addr: u128, val: u128) {
+    println!("mem[{}] = {}", addr, val);
+}
+
+/// Logs a single address being written to, along with its previous value if it had
+/// one.
+pub fn trace_write(addr: u128, old: Option<u128>, new: u128, width: usize) {
+    let old = match old {
+        Some(v) => v.to_string(),
+        None => "unset".into(),
+    };
+    println!(
+        "  {} ({}): {} -> {}",
+        format_hex(addr, width),
+        addr,
+        old,
+        new
+    );
+}
+
+/// Logs the part of a stored address pattern that a write replaced, along with its
+/// previous value.
+pub fn trace_overwrite(fixed: u128, bits: u128, old: u128, new: u128, width: usize) {
+    println!(
+        "    overwrites {}: {} -> {}",
+        format_pattern(fixed, bits, width),
+        old,
+        new
+    );
+}
+
+/// Prints every address and value in memory, sorted by address, in hex and binary.
+pub fn dump_memory(values: &HashMap<u128, u128>, width: usize) {
+    let mut entries: Vec<(&u128, &u128)> = values.iter().collect();
+    entries.sort();
+    for (addr, val) in entries {
+        println!(
+            "{} {} = {} {}",
+            format_hex(*addr, width),
+            format_bin(*addr, width),
+            format_hex(*val, width),
+            format_bin(*val, width)
+        );
+    }
+}
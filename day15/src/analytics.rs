@@ -0,0 +1,205 @@
+//! Provides statistics on the sequences spoken in the memory game, written as CSV for
+//! plotting.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::game::{MemoryGame, MAX_TABLE_LEN};
+
+/// Statistics on a single number spoken in a memory game.
+#[derive(Clone, Copy, Default)]
+pub struct NumberStats {
+    /// Number of times the number was spoken.
+    pub frequency: u32,
+    /// Turn the number was first spoken on, or 0 if it was never spoken.
+    pub first_turn: u32,
+    /// Most turns between two consecutive times the number was spoken, or 0 if it was
+    /// spoken less than twice.
+    pub longest_gap: u32,
+    /// Turn the number was last spoken on, or 0 if it was never spoken.
+    last_turn: u32,
+}
+
+impl NumberStats {
+    /// Records that the number was spoken on `turn`.
+    fn record(&mut self, turn: u32) {
+        self.frequency += 1;
+        if self.last_turn == 0 {
+            self.first_turn = turn;
+        } else {
+            self.longest_gap = self.longest_gap.max(turn - self.last_turn);
+        }
+        self.last_turn = turn;
+    }
+}
+
+/// Statistics on the first turns of a memory game.
+pub struct SequenceStats {
+    /// Starting numbers, as given on the command line.
+    pub label: String,
+    /// Number of turns analyzed.
+    pub turns: u32,
+    /// Statistics for each number below `turns`, up to `MAX_TABLE_LEN` numbers,
+    /// indexed by number.
+    pub numbers: Vec<NumberStats>,
+    /// Statistics for each spoken number too large for `numbers`.
+    pub large_numbers: BTreeMap<u32, NumberStats>,
+    /// Number of turns in each window of zero counts.
+    pub window: u32,
+    /// Number of zeros spoken in each window of `window` turns.
+    pub zero_counts: Vec<u32>,
+}
+
+impl SequenceStats {
+    /// Analyzes the first `turns` turns of a game opening with `start_nums`, counting
+    /// zeros in windows of `window` turns.
+    pub fn analyze(label: &str, start_nums: &[u32], turns: u32, window: u32) -> Self {
+        let window = window.max(1);
+        let mut stats = Self {
+            label: label.into(),
+            turns: 0,
+            numbers: vec![NumberStats::default(); (turns as usize).min(MAX_TABLE_LEN)],
+            large_numbers: BTreeMap::new(),
+            window,
+            zero_counts: Vec::new(),
+        };
+        for (i, n) in MemoryGame::with_capacity(start_nums, turns as usize)
+            .take(turns as usize)
+            .enumerate()
+        {
+            let turn = i as u32 + 1;
+            match stats.numbers.get_mut(n as usize) {
+                Some(num_stats) => num_stats.record(turn),
+                None => stats.large_numbers.entry(n).or_default().record(turn),
+            }
+
+            let window_i = (i as u32 / window) as usize;
+            if window_i >= stats.zero_counts.len() {
+                stats.zero_counts.push(0);
+            }
+            if n == 0 {
+                stats.zero_counts[window_i] += 1;
+            }
+            stats.turns = turn;
+        }
+        stats
+    }
+
+    /// Iterates over the statistics of every spoken number in ascending order.
+    pub fn spoken_numbers(&self) -> impl Iterator<Item = (u32, &NumberStats)> {
+        self.numbers
+            .iter()
+            .enumerate()
+            .map(|(n, num_stats)| (n as u32, num_stats))
+            .chain(
+                self.large_numbers
+                    .iter()
+                    .map(|(n, num_stats)| (*n, num_stats)),
+            )
+            .filter(|(_, num_stats)| num_stats.frequency > 0)
+    }
+
+    /// Gets the number with the longest gap between being spoken, along with the gap.
+    /// Ties go to the smallest number.
+    pub fn longest_gap_overall(&self) -> Option<(u32, u32)> {
+        self.spoken_numbers()
+            .filter(|(_, num_stats)| num_stats.longest_gap > 0)
+            .max_by_key(|(n, num_stats)| (num_stats.longest_gap, std::cmp::Reverse(*n)))
+            .map(|(n, num_stats)| (n, num_stats.longest_gap))
+    }
+
+    /// Counts the distinct numbers spoken.
+    pub fn distinct_numbers(&self) -> usize {
+        self.spoken_numbers().count()
+    }
+}
+
+/// Writes one row per run summarizing its statistics as CSV.
+///
+/// # Arguments
+///
+///  * `path` - Path of the CSV file to write.
+///  * `runs` - Statistics for each starting sequence.
+pub fn write_summary_csv<P>(path: P, runs: &[SequenceStats]) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(
+        out,
+        "sequence,turns,distinct_numbers,longest_gap_number,longest_gap,zero_density"
+    )?;
+    for run in runs.iter() {
+        let (gap_num, gap) = match run.longest_gap_overall() {
+            Some((n, gap)) => (n.to_string(), gap),
+            None => (String::new(), 0),
+        };
+        let zeros: u32 = run.zero_counts.iter().sum();
+        writeln!(
+            out,
+            "\"{}\",{},{},{},{},{}",
+            run.label,
+            run.turns,
+            run.distinct_numbers(),
+            gap_num,
+            gap,
+            zeros as f64 / run.turns.max(1) as f64
+        )?;
+    }
+    out.flush()
+}
+
+/// Writes the frequency, first turn, and longest gap of every spoken number as CSV.
+///
+/// # Arguments
+///
+///  * `path` - Path of the CSV file to write.
+///  * `runs` - Statistics for each starting sequence.
+pub fn write_numbers_csv<P>(path: P, runs: &[SequenceStats]) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "sequence,number,frequency,first_turn,longest_gap")?;
+    for run in runs.iter() {
+        for (n, num_stats) in run.spoken_numbers() {
+            writeln!(
+                out,
+                "\"{}\",{},{},{},{}",
+                run.label, n, num_stats.frequency, num_stats.first_turn, num_stats.longest_gap
+            )?;
+        }
+    }
+    out.flush()
+}
+
+/// Writes the fraction of zeros spoken in each window of turns as CSV.
+///
+/// # Arguments
+///
+///  * `path` - Path of the CSV file to write.
+///  * `runs` - Statistics for each starting sequence.
+pub fn write_zeros_csv<P>(path: P, runs: &[SequenceStats]) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "sequence,first_turn,last_turn,zero_density")?;
+    for run in runs.iter() {
+        for (i, zeros) in run.zero_counts.iter().enumerate() {
+            let first = i as u32 * run.window + 1;
+            let last = (first + run.window - 1).min(run.turns);
+            writeln!(
+                out,
+                "\"{}\",{},{},{}",
+                run.label,
+                first,
+                last,
+                *zeros as f64 / (last - first + 1) as f64
+            )?;
+        }
+    }
+    out.flush()
+}
@@ -0,0 +1,148 @@
+//! Provides running the memory game for many starting sequences at once.
+
+use std::path::Path;
+use std::thread;
+
+use utils::read_lines;
+
+use crate::game::MemoryGame;
+use crate::parse_start_nums;
+
+/// Reads lists of starting numbers from a file, one list per line.
+///
+/// # Returns
+///
+/// Each list of starting numbers, along with the line it was parsed from.
+pub fn read_sequences<P>(filename: P) -> Result<Vec<(String, Vec<u32>)>, String>
+where
+    P: AsRef<Path>,
+{
+    if !filename.as_ref().exists() {
+        return Err("File does not exists".into());
+    }
+    let lines = match read_lines(filename) {
+        Ok(l) => l,
+        Err(_) => return Err("Failed to read lines from file".into()),
+    };
+
+    let mut sequences = Vec::new();
+    for (i, line_res) in lines.enumerate() {
+        let line = match line_res {
+            Ok(l) => l,
+            Err(_) => return Err(format!("Failed to read line {}", i + 1)),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let nums = parse_start_nums(line).map_err(|e| format!("{} on line {}", e, i + 1))?;
+        sequences.push((line.to_string(), nums));
+    }
+    Ok(sequences)
+}
+
+/// Plays a single game, recording the number spoken on each target turn, or `None` if
+/// the game ends before reaching it.
+fn play_to_targets(start_nums: &[u32], targets: &[usize]) -> Vec<Option<u32>> {
+    let last = targets.iter().cloned().max().unwrap_or(0);
+    let mut spoken = vec![None; targets.len()];
+    for (i, n) in MemoryGame::with_capacity(start_nums, last)
+        .take(last)
+        .enumerate()
+    {
+        for (target, result) in targets.iter().zip(spoken.iter_mut()) {
+            if *target == i + 1 {
+                *result = Some(n);
+            }
+        }
+    }
+    spoken
+}
+
+/// Plays a game for every starting sequence, splitting the sequences across up to
+/// `threads` threads.
+///
+/// # Arguments
+///
+///  * `sequences` - Each list of starting numbers.
+///  * `targets` - Turns to record the spoken number for. Turns start at 1.
+///  * `threads` - Maximum number of threads to use.
+///
+/// # Returns
+///
+/// The number spoken on each target turn, for each sequence in order, or `None` for
+/// turns the game never reaches.
+pub fn run_batch(
+    sequences: &[Vec<u32>],
+    targets: &[usize],
+    threads: usize,
+) -> Vec<Vec<Option<u32>>> {
+    let threads = threads.max(1).min(sequences.len().max(1));
+    if threads == 1 {
+        return sequences
+            .iter()
+            .map(|nums| play_to_targets(nums, targets))
+            .collect();
+    }
+    let chunk_size = sequences.len().div_ceil(threads);
+    thread::scope(|s| {
+        let handles: Vec<_> = sequences
+            .chunks(chunk_size)
+            .map(|chunk| {
+                s.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|nums| play_to_targets(nums, targets))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("Memory game thread panicked"))
+            .collect()
+    })
+}
+
+/// Formats a spoken number for the results table, using "-" for unreached turns.
+fn format_spoken(n: Option<u32>) -> String {
+    match n {
+        Some(n) => n.to_string(),
+        None => "-".into(),
+    }
+}
+
+/// Prints a table with a row per starting sequence and a column per target turn.
+pub fn print_table(labels: &[&str], targets: &[usize], results: &[Vec<Option<u32>>]) {
+    let label_width = labels
+        .iter()
+        .map(|l| l.len())
+        .chain(std::iter::once("start".len()))
+        .max()
+        .unwrap_or(0);
+    let col_widths: Vec<usize> = targets
+        .iter()
+        .enumerate()
+        .map(|(i, target)| {
+            results
+                .iter()
+                .map(|row| format_spoken(row[i]).len())
+                .chain(std::iter::once(format!("turn {}", target).len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    print!("{:<width$}", "start", width = label_width);
+    for (target, width) in targets.iter().zip(col_widths.iter()) {
+        print!("  {:>width$}", format!("turn {}", target), width = width);
+    }
+    println!();
+    for (label, row) in labels.iter().zip(results.iter()) {
+        print!("{:<width$}", label, width = label_width);
+        for (n, width) in row.iter().zip(col_widths.iter()) {
+            print!("  {:>width$}", format_spoken(*n), width = width);
+        }
+        println!();
+    }
+}
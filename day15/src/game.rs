@@ -0,0 +1,84 @@
+//! Provides the memory game as an iterator over every spoken number.
+
+use std::collections::HashMap;
+
+/// Most numbers to track in a game's `Vec` of turns, enough for 30,000,000 turns. Larger
+/// numbers are tracked in a `HashMap` so long games don't reserve memory up front.
+pub const MAX_TABLE_LEN: usize = 1 << 25;
+
+/// Iterates over the numbers spoken in the memory game, starting with the starting
+/// numbers. Each later number is how many turns apart the previous number was last
+/// spoken, or 0 if it had not been spoken before.
+///
+/// The turn each number was last spoken is stored in a `Vec` indexed by number, sized
+/// for the expected number of turns up to `MAX_TABLE_LEN`. Every number spoken after
+/// the starting numbers is below the current turn, so only large starting numbers or
+/// long games fall back on a `HashMap`.
+pub struct MemoryGame {
+    start_nums: Vec<u32>,
+    /// Turn on which each number was last spoken, or 0 if it has not been spoken.
+    last_spoke: Vec<u32>,
+    /// Turn on which each number too large for `last_spoke` was last spoken.
+    overflow: HashMap<u32, u32>,
+    /// Number of turns taken so far.
+    turn: u32,
+    /// Number spoken on the latest turn.
+    prev: u32,
+}
+
+impl MemoryGame {
+    /// Creates a game that opens with `start_nums`, reserving memory for `turns` turns
+    /// up to `MAX_TABLE_LEN`.
+    pub fn with_capacity(start_nums: &[u32], turns: usize) -> Self {
+        Self {
+            start_nums: start_nums.to_vec(),
+            last_spoke: vec![0; turns.min(MAX_TABLE_LEN)],
+            overflow: HashMap::new(),
+            turn: 0,
+            prev: 0,
+        }
+    }
+
+    /// Gets the turn `n` was last spoken on, or 0 if it has not been spoken.
+    fn get_last_spoke(&self, n: u32) -> u32 {
+        match self.last_spoke.get(n as usize) {
+            Some(last) => *last,
+            None => self.overflow.get(&n).cloned().unwrap_or(0),
+        }
+    }
+
+    /// Records that `n` was spoken on `turn`.
+    fn set_last_spoke(&mut self, n: u32, turn: u32) {
+        match self.last_spoke.get_mut(n as usize) {
+            Some(last) => *last = turn,
+            None => {
+                self.overflow.insert(n, turn);
+            }
+        }
+    }
+}
+
+impl Iterator for MemoryGame {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.start_nums.is_empty() || self.turn == u32::MAX {
+            return None;
+        }
+        let cur = if (self.turn as usize) < self.start_nums.len() {
+            self.start_nums[self.turn as usize]
+        } else {
+            match self.get_last_spoke(self.prev) {
+                0 => 0,
+                last => self.turn - last,
+            }
+        };
+        if self.turn > 0 {
+            // Turn numbers start at 1, so the previous turn is `self.turn`.
+            self.set_last_spoke(self.prev, self.turn);
+        }
+        self.prev = cur;
+        self.turn += 1;
+        Some(cur)
+    }
+}
@@ -1,63 +1,226 @@
 #[macro_use]
 extern crate utils;
 
-use std::collections::HashMap;
+mod analytics;
+mod batch;
+mod game;
+
+use analytics::{write_numbers_csv, write_summary_csv, write_zeros_csv, SequenceStats};
+use game::MemoryGame;
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::thread;
+
+/// Command line options.
+struct Options {
+    /// Starting numbers, along with the text they were parsed from, unless running in
+    /// batch mode.
+    start_nums: Option<(String, Vec<u32>)>,
+    /// Directory to write sequence analytics to.
+    analyze_dir: Option<PathBuf>,
+    /// Number of turns to analyze.
+    analyze_turns: u32,
+    /// Number of turns in each window of zero densities.
+    window: u32,
+    /// File of starting sequences to run in batch mode, one per line.
+    batch_path: Option<PathBuf>,
+    /// Turns to report the spoken number for in batch mode.
+    targets: Vec<usize>,
+    /// Number of threads to use in batch mode.
+    threads: usize,
+}
 
 /// Prints usage statement for the executable.
 fn usage(args: Vec<String>) {
-    println!("Usage: {} starting_numbers", args[0]);
+    println!(
+        "Usage: {} (starting_numbers | --batch FILE [--targets N,...] [--threads N]) [--analyze DIR] [--analyze-turns N] [--window N]",
+        args[0]
+    );
 }
 
-/// Returns the number spoken on the `last_turn`th turn.
-fn memory_game(start_nums: &Vec<usize>, last_turn: usize) -> usize {
-    let mut last_spoke: HashMap<usize, usize> = HashMap::new();
-    for (i, n) in start_nums.iter().enumerate() {
-        if i == start_nums.len() - 1 {
-            break;
-        }
-        // Turn numbers start at 1, not 0.
-        last_spoke.insert(*n, i + 1);
-    }
-    // Assumes there is at least one start number.
-    let mut prev = *start_nums.last().unwrap();
-    for turn in (start_nums.len() + 1)..(last_turn + 1) {
-        let cur: usize = match last_spoke.get(&prev) {
-            Some(last) => turn - 1 - last,
-            None => 0,
-        };
-        last_spoke.insert(prev, turn - 1);
-        prev = cur;
+/// Parses a comma-separated list of starting numbers.
+pub fn parse_start_nums(s: &str) -> Result<Vec<u32>, String> {
+    let mut nums = Vec::new();
+    for num in s.split(',') {
+        match num.trim().parse::<u32>() {
+            Ok(n) => nums.push(n),
+            Err(_) => return Err(format!("Failed to parse starting numbers \"{}\"", s)),
+        }
     }
-    prev
+    Ok(nums)
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        usage(args);
-        process::exit(1);
+/// Parses a comma-separated list of target turns, which must all be positive and no
+/// more than the last turn a game can reach.
+fn parse_targets(s: &str) -> Option<Vec<usize>> {
+    let mut targets = Vec::new();
+    for t in s.split(',') {
+        match t.trim().parse::<u32>() {
+            Ok(n) if n > 0 => targets.push(n as usize),
+            _ => return None,
+        }
     }
-    let start_nums: Vec<usize> = {
-        let mut nums = Vec::new();
-        for num in args[1].split(',') {
-            match num.parse::<usize>() {
-                Ok(n) => nums.push(n),
-                Err(_) => {
-                    eprintln!("Failed to parse starting numbers");
-                    process::exit(1);
+    Some(targets)
+}
+
+/// Parses command line arguments.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options {
+        start_nums: None,
+        analyze_dir: None,
+        analyze_turns: 2020,
+        window: 100,
+        batch_path: None,
+        targets: vec![2020, 30_000_000],
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--analyze" => match iter.next() {
+                Some(dir) => opts.analyze_dir = Some(PathBuf::from(dir)),
+                None => return Err("Missing value for --analyze".into()),
+            },
+            "--analyze-turns" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => opts.analyze_turns = n,
+                None => return Err("Expected a number for --analyze-turns".into()),
+            },
+            "--window" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) if n > 0 => opts.window = n,
+                _ => return Err("Expected a positive number for --window".into()),
+            },
+            "--batch" => match iter.next() {
+                Some(path) => opts.batch_path = Some(PathBuf::from(path)),
+                None => return Err("Missing value for --batch".into()),
+            },
+            "--targets" => match iter.next().and_then(|t| parse_targets(t)) {
+                Some(targets) => opts.targets = targets,
+                None => {
+                    return Err(format!(
+                        "Expected turns from 1 to {} separated by commas for --targets",
+                        u32::MAX
+                    ))
                 }
+            },
+            "--threads" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) if n > 0 => opts.threads = n,
+                _ => return Err("Expected a positive number for --threads".into()),
+            },
+            _ if arg.starts_with("--") => return Err(format!("Unexpected argument: {}", arg)),
+            _ if opts.start_nums.is_none() => {
+                opts.start_nums = Some((arg.clone(), parse_start_nums(arg)?))
             }
+            _ => return Err(format!("Unexpected argument: {}", arg)),
         }
-        nums
-    };
+    }
+    match (&opts.start_nums, &opts.batch_path) {
+        (None, None) => Err("Missing starting numbers".into()),
+        (Some(_), Some(_)) => Err("Starting numbers can't be given with --batch".into()),
+        _ => Ok(opts),
+    }
+}
+
+/// Returns the number spoken on the `last_turn`th turn.
+fn memory_game(start_nums: &[u32], last_turn: usize) -> Option<u32> {
+    MemoryGame::with_capacity(start_nums, last_turn).nth(last_turn.checked_sub(1)?)
+}
+
+/// Analyzes every starting sequence and writes the results as CSV files in `dir`.
+fn write_analytics(
+    dir: &Path,
+    sequences: &[(String, Vec<u32>)],
+    opts: &Options,
+) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let runs: Vec<SequenceStats> = sequences
+        .iter()
+        .map(|(label, nums)| SequenceStats::analyze(label, nums, opts.analyze_turns, opts.window))
+        .collect();
+    let summary_path = dir.join("summary.csv");
+    write_summary_csv(&summary_path, &runs)
+        .map_err(|e| format!("Failed to write {}: {}", summary_path.display(), e))?;
+    let numbers_path = dir.join("numbers.csv");
+    write_numbers_csv(&numbers_path, &runs)
+        .map_err(|e| format!("Failed to write {}: {}", numbers_path.display(), e))?;
+    let zeros_path = dir.join("zeros.csv");
+    write_zeros_csv(&zeros_path, &runs)
+        .map_err(|e| format!("Failed to write {}: {}", zeros_path.display(), e))?;
+    Ok(())
+}
 
-    timed_section!("Part 1", { memory_game(&start_nums, 2020) }, |v| {
-        println!("At turn 2020: {}", v);
+/// Runs both parts of the puzzle.
+fn run_parts(start_nums: &[u32]) {
+    timed_section!("Part 1", { memory_game(start_nums, 2020) }, |v| {
+        if let Some(n) = v {
+            println!("At turn 2020: {}", n);
+        }
     });
 
-    timed_section!("Part 2", { memory_game(&start_nums, 30_000_000) }, |v| {
-        println!("At 30000000 turns: {}", v);
+    timed_section!("Part 2", { memory_game(start_nums, 30_000_000) }, |v| {
+        if let Some(n) = v {
+            println!("At 30000000 turns: {}", n);
+        }
     });
 }
+
+/// Runs every starting sequence to each target turn in parallel and prints a table of
+/// the results.
+fn run_batch(sequences: &[(String, Vec<u32>)], opts: &Options) {
+    let nums: Vec<Vec<u32>> = sequences.iter().map(|(_, n)| n.clone()).collect();
+    timed_section!(
+        "Batch",
+        { batch::run_batch(&nums, &opts.targets, opts.threads) },
+        |results: Vec<Vec<Option<u32>>>| {
+            let labels: Vec<&str> = sequences.iter().map(|(l, _)| l.as_str()).collect();
+            batch::print_table(&labels, &opts.targets, &results);
+        }
+    );
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let opts = match parse_args(&args) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}", e);
+            usage(args);
+            process::exit(1);
+        }
+    };
+
+    let sequences = match (&opts.batch_path, &opts.start_nums) {
+        (Some(path), _) => match batch::read_sequences(path) {
+            Ok(sequences) => {
+                run_batch(&sequences, &opts);
+                sequences
+            }
+            Err(e) => {
+                eprintln!("Failed to read starting sequences: {}", e);
+                process::exit(1);
+            }
+        },
+        (None, Some(start)) => {
+            run_parts(&start.1);
+            vec![start.clone()]
+        }
+        (None, None) => unreachable!("parse_args requires starting numbers or --batch"),
+    };
+
+    if let Some(dir) = &opts.analyze_dir {
+        timed_section!(
+            "Analytics",
+            { write_analytics(dir, &sequences, &opts) },
+            |v| {
+                match v {
+                    Ok(()) => println!("Wrote analytics to {}", dir.display()),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        process::exit(1);
+                    }
+                }
+            }
+        );
+    }
+}
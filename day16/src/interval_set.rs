@@ -0,0 +1,105 @@
+//! Provides a set of values stored as merged intervals.
+
+use std::fmt;
+
+/// A set of values stored as sorted, non-overlapping, non-adjacent inclusive intervals.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct IntervalSet {
+    intervals: Vec<(u32, u32)>,
+}
+
+impl IntervalSet {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self {
+            intervals: Vec::new(),
+        }
+    }
+
+    /// Creates a set containing every value.
+    pub fn full() -> Self {
+        Self {
+            intervals: vec![(0, u32::MAX)],
+        }
+    }
+
+    /// Gets the intervals in the set, sorted in ascending order.
+    pub fn intervals(&self) -> &[(u32, u32)] {
+        &self.intervals
+    }
+
+    /// Adds every value from `low` to `high` inclusive.
+    pub fn insert(&mut self, low: u32, high: u32) {
+        if low > high {
+            return;
+        }
+        self.intervals.push((low, high));
+        self.intervals.sort_unstable();
+        let mut merged: Vec<(u32, u32)> = Vec::with_capacity(self.intervals.len());
+        for (lo, hi) in self.intervals.iter() {
+            match merged.last_mut() {
+                Some(last) if *lo <= last.1.saturating_add(1) => last.1 = last.1.max(*hi),
+                _ => merged.push((*lo, *hi)),
+            }
+        }
+        self.intervals = merged;
+    }
+
+    /// Removes every value from `low` to `high` inclusive.
+    pub fn remove(&mut self, low: u32, high: u32) {
+        if low > high {
+            return;
+        }
+        let mut kept = Vec::with_capacity(self.intervals.len() + 1);
+        for (lo, hi) in self.intervals.iter() {
+            if *hi < low || *lo > high {
+                kept.push((*lo, *hi));
+                continue;
+            }
+            if *lo < low {
+                kept.push((*lo, low - 1));
+            }
+            if *hi > high {
+                kept.push((high + 1, *hi));
+            }
+        }
+        self.intervals = kept;
+    }
+
+    /// Adds every value in `other`.
+    pub fn extend(&mut self, other: &IntervalSet) {
+        for (lo, hi) in other.intervals.iter() {
+            self.insert(*lo, *hi);
+        }
+    }
+
+    /// Checks whether the set contains `v`.
+    pub fn contains(&self, v: u32) -> bool {
+        // Find the last interval starting at or before `v`.
+        let i = self.intervals.partition_point(|(lo, _)| *lo <= v);
+        i > 0 && v <= self.intervals[i - 1].1
+    }
+}
+
+/// Formats inclusive bounds the same way rule ranges are written.
+pub fn format_interval(low: u32, high: u32) -> String {
+    if low == high {
+        low.to_string()
+    } else if high == u32::MAX {
+        format!("{}-", low)
+    } else {
+        format!("{}-{}", low, high)
+    }
+}
+
+impl fmt::Display for IntervalSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (lo, hi)) in self.intervals.iter().enumerate() {
+            if i > 0 {
+                write!(f, " or ")?;
+            }
+            write!(f, "{}", format_interval(*lo, *hi))?;
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
+mod interval_set;
+pub mod report;
 pub mod sorting;
 mod ticket;
 mod ticket_field_rule;
 pub mod ticket_parser;
 
+pub use interval_set::IntervalSet;
 pub use ticket::Ticket;
 pub use ticket_field_rule::TicketFieldRule;
@@ -2,18 +2,68 @@
 extern crate utils;
 
 use std::env;
+use std::path::PathBuf;
 use std::process;
 
+use day16::report::{self, InvalidTicket};
 use day16::sorting::{get_valid_tickets, FieldSorter};
 use day16::{ticket_parser, Ticket, TicketFieldRule};
 
+/// Default number of field orderings to search for.
+const DEFAULT_MAX_ORDERINGS: usize = 100;
+
+/// Command line options.
+struct Options {
+    /// Path to the input file.
+    input_path: String,
+    /// Number of field orderings to stop searching after.
+    max_orderings: usize,
+    /// Whether to print why each invalid ticket is invalid.
+    report: bool,
+    /// Path to write the invalid ticket report to as CSV.
+    report_csv: Option<PathBuf>,
+}
+
 /// Prints usage statement for the executable.
 fn usage(args: Vec<String>) {
-    println!("Usage: {} input_file", args[0]);
+    println!(
+        "Usage: {} input_file [--max-orderings N] [--report] [--report-csv PATH]",
+        args[0]
+    );
+}
+
+/// Parses command line arguments.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let input_path = match args.get(1) {
+        Some(p) => p.clone(),
+        None => return Err("Missing input file".into()),
+    };
+    let mut opts = Options {
+        input_path,
+        max_orderings: DEFAULT_MAX_ORDERINGS,
+        report: false,
+        report_csv: None,
+    };
+    let mut iter = args.iter().skip(2);
+    while let Some(flag) = iter.next() {
+        match flag.as_str() {
+            "--max-orderings" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) if n > 0 => opts.max_orderings = n,
+                _ => return Err("Expected a positive number for --max-orderings".into()),
+            },
+            "--report" => opts.report = true,
+            "--report-csv" => match iter.next() {
+                Some(path) => opts.report_csv = Some(PathBuf::from(path)),
+                None => return Err("Missing value for --report-csv".into()),
+            },
+            _ => return Err(format!("Unexpected argument: {}", flag)),
+        }
+    }
+    Ok(opts)
 }
 
 /// Calculates the product of all fields starting with "departure" for a ticket.
-fn mult_depart_values(ticket: &Ticket, ordered_fields: &Vec<&TicketFieldRule>) -> usize {
+fn mult_depart_values(ticket: &Ticket, ordered_fields: &[&TicketFieldRule]) -> usize {
     let mut prod: usize = 1;
     for (i, v) in ticket.field_iter().enumerate() {
         let rule = ordered_fields[i];
@@ -24,15 +74,44 @@ fn mult_depart_values(ticket: &Ticket, ordered_fields: &Vec<&TicketFieldRule>) -
     prod
 }
 
+/// Prints each invalid ticket with its offending values and the rule ranges they
+/// came closest to.
+fn print_report(invalid: &[InvalidTicket]) {
+    println!("{} invalid tickets", invalid.len());
+    for ticket in invalid.iter() {
+        for value in ticket.values.iter() {
+            let nearest: Vec<String> = value
+                .nearest
+                .iter()
+                .map(|m| format!("\"{}\" {} by {}", m.rule, m.range_string(), m.distance))
+                .collect();
+            println!(
+                "Line {}: value {} at position {} (nearest: {})",
+                ticket.line,
+                value.value,
+                value.position,
+                if nearest.is_empty() {
+                    "none".into()
+                } else {
+                    nearest.join(", ")
+                }
+            );
+        }
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        usage(args);
-        process::exit(1);
-    }
-    let input_path = String::from(&args[1]);
+    let opts = match parse_args(&args) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}", e);
+            usage(args);
+            process::exit(1);
+        }
+    };
     let (ticket_rules, your_ticket, other_tickets) =
-        match ticket_parser::read_ticket_data(&input_path) {
+        match ticket_parser::read_ticket_data(&opts.input_path) {
             Ok(v) => v,
             Err(e) => {
                 eprintln!("Failed to read ticket data: {}", e);
@@ -49,15 +128,63 @@ fn main() {
         }
     );
 
+    if opts.report || opts.report_csv.is_some() {
+        timed_section!(
+            "Invalid tickets",
+            { report::find_invalid_tickets(&ticket_rules, &other_tickets) },
+            |invalid: Vec<InvalidTicket>| {
+                if opts.report {
+                    print_report(&invalid);
+                }
+                if let Some(path) = &opts.report_csv {
+                    match report::write_csv(path, &invalid) {
+                        Ok(()) => println!("Wrote report to {}", path.display()),
+                        Err(e) => eprintln!("Failed to write {}: {}", path.display(), e),
+                    }
+                }
+            }
+        );
+    }
+
     timed_section!(
         "Part 2",
         {
-            let mut sorter = FieldSorter::new(&ticket_rules, &valid_tickets);
-            let ordered_fields = sorter.get_field_ordering();
-            mult_depart_values(&your_ticket, &ordered_fields)
+            if your_ticket.values.len() != ticket_rules.len() {
+                Err(format!(
+                    "Your ticket has {} fields, expected {}",
+                    your_ticket.values.len(),
+                    ticket_rules.len()
+                ))
+            } else {
+                FieldSorter::new(&ticket_rules, &valid_tickets)
+                    .and_then(|sorter| sorter.get_field_orderings(opts.max_orderings))
+            }
         },
-        |v| {
-            println!("Product of departure values: {}", v);
+        |v: Result<Vec<Vec<&TicketFieldRule>>, String>| {
+            match v {
+                Ok(orderings) => {
+                    if orderings.len() >= opts.max_orderings {
+                        println!("Stopped after finding {} field orderings", orderings.len());
+                    } else if orderings.len() > 1 {
+                        println!("Found {} field orderings", orderings.len());
+                    }
+                    for ordered_fields in orderings.iter() {
+                        if orderings.len() > 1 {
+                            let names: Vec<String> =
+                                ordered_fields.iter().map(|r| r.get_name()).collect();
+                            println!("Field ordering: {}", names.join(", "));
+                        }
+                        println!(
+                            "Product of departure values: {}",
+                            mult_depart_values(&your_ticket, ordered_fields)
+                        );
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            }
         }
     );
 }
@@ -0,0 +1,138 @@
+//! Provides a report of invalid tickets and why each one is invalid.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::interval_set::format_interval;
+use crate::sorting::accepted_values;
+use crate::{Ticket, TicketFieldRule};
+
+/// A rule range that a value fell outside of.
+pub struct NearMiss {
+    /// Name of the rule the range belongs to.
+    pub rule: String,
+    pub low: u32,
+    pub high: u32,
+    /// How far the value is from the range.
+    pub distance: u32,
+}
+
+/// A value that no rule accepts.
+pub struct InvalidValue {
+    /// Position of the field in the ticket, starting at 1.
+    pub position: usize,
+    pub value: u32,
+    /// The rule ranges closest to the value, which are all equally close.
+    pub nearest: Vec<NearMiss>,
+}
+
+/// A ticket with at least one value that no rule accepts.
+pub struct InvalidTicket {
+    /// Line number the ticket was read from, starting at 1.
+    pub line: usize,
+    pub values: Vec<InvalidValue>,
+}
+
+impl NearMiss {
+    /// Formats the missed range the same way rules are written.
+    pub fn range_string(&self) -> String {
+        format_interval(self.low, self.high)
+    }
+}
+
+/// Finds the rule ranges closest to a value, keeping every range tied for closest.
+fn nearest_ranges(ticket_rules: &[TicketFieldRule], v: u32) -> Vec<NearMiss> {
+    let mut nearest: Vec<NearMiss> = Vec::new();
+    for rule in ticket_rules.iter() {
+        for (low, high) in rule.get_ranges().intervals().iter() {
+            let distance = if v < *low {
+                low - v
+            } else {
+                v.saturating_sub(*high)
+            };
+            if let Some(best) = nearest.first() {
+                if distance > best.distance {
+                    continue;
+                }
+                if distance < best.distance {
+                    nearest.clear();
+                }
+            }
+            nearest.push(NearMiss {
+                rule: rule.get_name(),
+                low: *low,
+                high: *high,
+                distance,
+            });
+        }
+    }
+    nearest
+}
+
+/// Finds every ticket with a value that no rule accepts, along with the offending
+/// values and the rule ranges they came closest to.
+pub fn find_invalid_tickets(
+    ticket_rules: &[TicketFieldRule],
+    tickets: &[Ticket],
+) -> Vec<InvalidTicket> {
+    let any_rule = accepted_values(ticket_rules);
+    let mut invalid = Vec::new();
+    for ticket in tickets.iter() {
+        let values: Vec<InvalidValue> = ticket
+            .field_iter()
+            .enumerate()
+            .filter(|(_, v)| !any_rule.contains(**v))
+            .map(|(i, v)| InvalidValue {
+                position: i + 1,
+                value: *v,
+                nearest: nearest_ranges(ticket_rules, *v),
+            })
+            .collect();
+        if !values.is_empty() {
+            invalid.push(InvalidTicket {
+                line: ticket.line,
+                values,
+            });
+        }
+    }
+    invalid
+}
+
+/// Writes invalid tickets as CSV, with a row per offending value and nearest range.
+/// Rule names are quoted, with any quotes in them doubled.
+///
+/// # Arguments
+///
+///  * `path` - Path of the CSV file to write.
+///  * `invalid` - Invalid tickets found by `find_invalid_tickets`.
+pub fn write_csv<P>(path: P, invalid: &[InvalidTicket]) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(
+        out,
+        "line,position,value,nearest_rule,nearest_range,distance"
+    )?;
+    for ticket in invalid.iter() {
+        for value in ticket.values.iter() {
+            if value.nearest.is_empty() {
+                writeln!(out, "{},{},{},,,", ticket.line, value.position, value.value)?;
+            }
+            for miss in value.nearest.iter() {
+                writeln!(
+                    out,
+                    "{},{},{},\"{}\",{},{}",
+                    ticket.line,
+                    value.position,
+                    value.value,
+                    miss.rule.replace('"', "\"\""),
+                    miss.range_string(),
+                    miss.distance
+                )?;
+            }
+        }
+    }
+    out.flush()
+}
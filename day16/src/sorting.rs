@@ -1,41 +1,40 @@
 //! Provides a utility for sorting tickets and fields.
 
-use std::collections::{HashMap, HashSet};
+use crate::{IntervalSet, Ticket, TicketFieldRule};
 
-use crate::{Ticket, TicketFieldRule};
-
-/// Finds the field ordering for a set of tickets and a set of unordered field rules.
-//
-/// Assumes there is exactly one valid field ordering and at least one valid ticket.
+/// Finds the field orderings for a set of tickets and a set of unordered field rules.
+///
+/// Fields are matched to rules as a bipartite matching problem. Every ordering is found
+/// by backtracking, only trying assignments that still leave a complete matching for
+/// the remaining fields.
 pub struct FieldSorter<'a> {
     ticket_rules: Vec<&'a TicketFieldRule>,
-    valid_tickets: Vec<&'a Ticket>,
-    found_order: bool,
-    ordering: Vec<&'a TicketFieldRule>,
-    /// Maps a field index to it's possible rules.
-    per_field_maybes: Vec<HashSet<&'a TicketFieldRule>>,
-    /// Maps a rule to it's possible field indices.
-    per_rule_maybes: HashMap<&'a TicketFieldRule, HashSet<usize>>,
+    /// Maps a field index to the indices of the rules valid for every value in it.
+    candidates: Vec<Vec<usize>>,
+}
+
+/// Gets every value accepted by at least one rule. A value is valid if any rule
+/// accepts it, so tickets are checked against this union of all rules.
+pub fn accepted_values(ticket_rules: &[TicketFieldRule]) -> IntervalSet {
+    let mut any_rule = IntervalSet::new();
+    for rule in ticket_rules.iter() {
+        any_rule.extend(rule.get_ranges());
+    }
+    any_rule
 }
 
 /// Finds all the valid tickets and calculates the error rate.
 pub fn get_valid_tickets<'a>(
-    ticket_rules: &Vec<TicketFieldRule>,
-    tickets: &'a Vec<Ticket>,
+    ticket_rules: &[TicketFieldRule],
+    tickets: &'a [Ticket],
 ) -> (Vec<&'a Ticket>, u32) {
+    let any_rule = accepted_values(ticket_rules);
     let mut valid = Vec::new();
     let mut err_rate = 0;
     for ticket in tickets.iter() {
         let mut is_valid_ticket = true;
         for v in ticket.field_iter() {
-            let mut is_valid = false;
-            for rule in ticket_rules {
-                if rule.check_value(*v) {
-                    is_valid = true;
-                    break;
-                }
-            }
-            if !is_valid {
+            if !any_rule.contains(*v) {
                 err_rate += v;
                 is_valid_ticket = false;
             }
@@ -47,137 +46,227 @@ pub fn get_valid_tickets<'a>(
     (valid, err_rate)
 }
 
-impl<'a> FieldSorter<'a> {
-    pub fn new(
-        ticket_rules: &'a Vec<TicketFieldRule>,
-        valid_tickets: &'a Vec<&'a Ticket>,
-    ) -> FieldSorter<'a> {
-        assert!(!valid_tickets.is_empty(), "no tickets provided");
-        let mut per_field_maybes = Vec::new();
-        let mut per_rule_maybes = HashMap::new();
-        for rule1 in ticket_rules.iter() {
-            let mut possible_rules: HashSet<&TicketFieldRule> = HashSet::new();
-            let mut possible_fields: HashSet<usize> = HashSet::new();
-            for (i2, rule2) in ticket_rules.iter().enumerate() {
-                possible_rules.insert(rule2);
-                possible_fields.insert(i2);
-            }
-            per_field_maybes.push(possible_rules);
-            per_rule_maybes.insert(rule1, possible_fields);
-        }
-        assert!(per_field_maybes.len() == per_rule_maybes.len());
-        assert!(per_field_maybes[0].len() == per_field_maybes.len());
-        assert!(per_field_maybes[0].len() == per_rule_maybes.iter().next().unwrap().1.len());
+/// Tracks which rules are matched to which fields while searching for orderings.
+struct Matching {
+    /// Maps a field index to its assigned rule index.
+    field_rule: Vec<Option<usize>>,
+    /// Maps a rule index to its assigned field index.
+    rule_field: Vec<Option<usize>>,
+}
+
+impl Matching {
+    /// Creates a matching with no fields assigned.
+    fn new(num_fields: usize, num_rules: usize) -> Self {
         Self {
-            ticket_rules: ticket_rules.iter().collect(),
-            valid_tickets: valid_tickets.iter().cloned().collect(),
-            found_order: false,
-            ordering: Vec::new(),
-            per_field_maybes: per_field_maybes,
-            per_rule_maybes: per_rule_maybes,
+            field_rule: vec![None; num_fields],
+            rule_field: vec![None; num_rules],
         }
     }
 
-    /// Determine that a field cannot be defined by a rule.
-    fn remove_relation(&mut self, field_i: usize, rule: &'a TicketFieldRule) -> bool {
-        let possible_rules = &mut self.per_field_maybes[field_i];
-        let possible_fields = &mut self.per_rule_maybes.get_mut(rule).unwrap();
-        possible_rules.remove(rule) | possible_fields.remove(&field_i)
+    /// Matches a field to a rule.
+    fn assign(&mut self, field_i: usize, rule_i: usize) {
+        self.field_rule[field_i] = Some(rule_i);
+        self.rule_field[rule_i] = Some(field_i);
+    }
+
+    /// Removes the rule matched to a field, if any.
+    fn unassign(&mut self, field_i: usize) {
+        if let Some(rule_i) = self.field_rule[field_i].take() {
+            self.rule_field[rule_i] = None;
+        }
     }
+}
 
-    /// Updates relations so a field is marked as valid only for the rule `valid_rule`.
-    fn field_valid_only_for(&mut self, valid_rule: &'a TicketFieldRule) -> bool {
-        let mut change = false;
-        assert!(self.per_rule_maybes[valid_rule].len() == 1);
-        let field_i = *self.per_rule_maybes[valid_rule].iter().next().unwrap();
-        // Not using an iter over `self.ticket_rules` to avoid a second mutable borrow.
-        for i in 0..self.ticket_rules.len() {
-            let rule = self.ticket_rules[i];
-            if *rule != *valid_rule {
-                change |= self.remove_relation(field_i, rule);
+impl<'a> FieldSorter<'a> {
+    /// Creates a sorter for fields of `valid_tickets` as found by `get_valid_tickets`.
+    /// Every ticket must have one value per rule.
+    pub fn new(
+        ticket_rules: &'a [TicketFieldRule],
+        valid_tickets: &[&Ticket],
+    ) -> Result<FieldSorter<'a>, String> {
+        let num_fields = ticket_rules.len();
+        for (i, ticket) in valid_tickets.iter().enumerate() {
+            if ticket.values.len() != num_fields {
+                return Err(format!(
+                    "Valid ticket {} has {} fields, expected {}",
+                    i + 1,
+                    ticket.values.len(),
+                    num_fields
+                ));
             }
         }
-        change
+        let candidates = (0..num_fields)
+            .map(|field_i| {
+                (0..ticket_rules.len())
+                    .filter(|rule_i| {
+                        valid_tickets
+                            .iter()
+                            .all(|t| ticket_rules[*rule_i].check_value(t.values[field_i]))
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(Self {
+            ticket_rules: ticket_rules.iter().collect(),
+            candidates,
+        })
     }
 
-    /// Updates relations so a rule is marked as valid only for the field index `valid_field`.
-    fn rule_valid_only_for(&mut self, valid_field: usize) -> bool {
-        let mut change = false;
-        assert!(self.per_field_maybes[valid_field].len() == 1);
-        let rule = *self.per_field_maybes[valid_field].iter().next().unwrap();
-        for i in 0..self.per_field_maybes.len() {
-            if i != valid_field {
-                change |= self.remove_relation(i, rule);
+    /// Tries to match `field_i` to a free rule, moving other fields to different rules
+    /// along an augmenting path if needed.
+    fn augment(&self, field_i: usize, matching: &mut Matching, visited: &mut [bool]) -> bool {
+        for rule_i in self.candidates[field_i].iter() {
+            if visited[*rule_i] {
+                continue;
+            }
+            visited[*rule_i] = true;
+            let free = match matching.rule_field[*rule_i] {
+                None => true,
+                Some(other) => self.augment(other, matching, visited),
+            };
+            if free {
+                matching.assign(field_i, *rule_i);
+                return true;
             }
         }
-        change
+        false
     }
 
-    /// Prunes redundant relations.
+    /// Extends `fixed` into a maximum matching without changing the fields it already
+    /// assigns.
     ///
-    /// Returns whether a single ordering has been found.
-    fn prune(&mut self) -> bool {
-        let mut no_variance = true;
-        let mut changes = true;
-        while changes {
-            no_variance = true;
-            changes = false;
-            for i in 0..self.per_field_maybes.len() {
-                if self.per_field_maybes[i].len() == 1 {
-                    changes |= self.rule_valid_only_for(i);
-                } else {
-                    no_variance = false;
-                }
+    /// # Returns
+    ///
+    /// The maximum matching and the fields left unmatched by it.
+    fn max_matching(&self, fixed: &Matching) -> (Matching, Vec<usize>) {
+        let mut matching = Matching::new(self.candidates.len(), self.ticket_rules.len());
+        let mut visited = vec![false; self.ticket_rules.len()];
+        // Fixed rules are marked as visited so augmenting paths never reassign them.
+        for (field_i, rule_i) in fixed.field_rule.iter().enumerate() {
+            if let Some(rule_i) = rule_i {
+                matching.assign(field_i, *rule_i);
+            }
+        }
+        let mut unmatched = Vec::new();
+        for field_i in 0..self.candidates.len() {
+            if matching.field_rule[field_i].is_some() {
+                continue;
+            }
+            for (rule_i, v) in visited.iter_mut().enumerate() {
+                *v = fixed.rule_field[rule_i].is_some();
+            }
+            if !self.augment(field_i, &mut matching, &mut visited) {
+                unmatched.push(field_i);
+            }
+        }
+        (matching, unmatched)
+    }
 
-                let rule = self.ticket_rules[i];
-                if self.per_rule_maybes[rule].len() == 1 {
-                    changes |= self.field_valid_only_for(rule);
-                } else {
-                    no_variance = false;
+    /// Describes why `field_i` cannot be matched, given a maximum `matching` that leaves
+    /// it unmatched. Fields reachable by alternating paths form a group with fewer
+    /// candidate rules than fields.
+    fn describe_conflict(&self, field_i: usize, matching: &Matching) -> String {
+        let mut fields = vec![field_i];
+        let mut rules: Vec<usize> = Vec::new();
+        let mut i = 0;
+        while i < fields.len() {
+            for rule_i in self.candidates[fields[i]].iter() {
+                if rules.contains(rule_i) {
+                    continue;
+                }
+                rules.push(*rule_i);
+                // The matching is maximum, so every reachable rule is matched.
+                if let Some(other) = matching.rule_field[*rule_i] {
+                    if !fields.contains(&other) {
+                        fields.push(other);
+                    }
                 }
             }
+            i += 1;
+        }
+        fields.sort_unstable();
+        rules.sort_unstable();
+        let field_names: Vec<String> = fields.iter().map(|f| (f + 1).to_string()).collect();
+        let rule_names: Vec<String> = rules
+            .iter()
+            .map(|r| format!("\"{}\"", self.ticket_rules[*r].get_name()))
+            .collect();
+        if rule_names.is_empty() {
+            format!(
+                "No valid field ordering: no rule fits field position {}",
+                field_names.join(", ")
+            )
+        } else {
+            format!(
+                "No valid field ordering: field positions {} only fit the {} rule(s) {}",
+                field_names.join(", "),
+                rules.len(),
+                rule_names.join(", ")
+            )
         }
-        no_variance
     }
 
-    /// Finds the ordering of fields using valid tickets found by `get_valid_tickets`.
-    pub fn get_field_ordering<'b>(&'b mut self) -> Vec<&'b TicketFieldRule> {
-        if self.found_order {
-            return self.ordering.iter().cloned().collect();
+    /// Assigns fields one at a time, only keeping assignments that leave a complete
+    /// matching, and records each complete ordering.
+    fn search(&self, matching: &mut Matching, orderings: &mut Vec<Vec<usize>>, limit: usize) {
+        if orderings.len() >= limit {
+            return;
         }
-        let valid_tickets: Vec<&Ticket> = self.valid_tickets.iter().map(|t| *t).collect();
-        let mut ordering_found = false;
-        for ticket in valid_tickets.iter() {
-            for (field_i, v) in ticket.field_iter().enumerate() {
-                let possible_rules = &mut self.per_field_maybes[field_i];
-                let mut to_remove = Vec::new();
-                for rule in possible_rules.iter() {
-                    if !rule.check_value(*v) {
-                        to_remove.push(*rule);
-                    }
-                }
-                for rule in to_remove.iter() {
-                    possible_rules.remove(*rule);
-                }
+        // Branch on the unassigned field with the fewest remaining rules.
+        let next = (0..self.candidates.len())
+            .filter(|f| matching.field_rule[*f].is_none())
+            .min_by_key(|f| {
+                self.candidates[*f]
+                    .iter()
+                    .filter(|r| matching.rule_field[**r].is_none())
+                    .count()
+            });
+        let field_i = match next {
+            Some(f) => f,
+            None => {
+                orderings.push(matching.field_rule.iter().map(|r| r.unwrap()).collect());
+                return;
+            }
+        };
+        for rule_i in self.candidates[field_i].iter() {
+            if matching.rule_field[*rule_i].is_some() {
                 continue;
             }
-            if self.prune() {
-                ordering_found = true;
-                break;
+            matching.assign(field_i, *rule_i);
+            if self.max_matching(matching).1.is_empty() {
+                self.search(matching, orderings, limit);
+            }
+            matching.unassign(field_i);
+            if orderings.len() >= limit {
+                return;
             }
         }
-        assert!(ordering_found);
+    }
 
-        // Assumes an ordering has been found by this point.
-        for _ in 0..self.ticket_rules.len() {
-            self.ordering.push(self.ticket_rules[0]);
-        }
-        for (rule, possible_fields) in self.per_rule_maybes.iter() {
-            // All `HashSet`s should have a length of 1.
-            let field_i = *possible_fields.iter().next().unwrap();
-            self.ordering[field_i] = rule;
+    /// Finds the valid orderings of fields, stopping after `limit` orderings.
+    ///
+    /// # Returns
+    ///
+    /// The rule for each field position, for each ordering found. Returns an error
+    /// naming the conflicting fields if no ordering exists.
+    pub fn get_field_orderings(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<Vec<&'a TicketFieldRule>>, String> {
+        let empty = Matching::new(self.candidates.len(), self.ticket_rules.len());
+        let (matching, unmatched) = self.max_matching(&empty);
+        if let Some(field_i) = unmatched.first() {
+            return Err(self.describe_conflict(*field_i, &matching));
         }
-
-        self.ordering.iter().cloned().collect()
+        let mut orderings = Vec::new();
+        self.search(
+            &mut Matching::new(self.candidates.len(), self.ticket_rules.len()),
+            &mut orderings,
+            limit,
+        );
+        Ok(orderings
+            .iter()
+            .map(|o| o.iter().map(|rule_i| self.ticket_rules[*rule_i]).collect())
+            .collect())
     }
 }
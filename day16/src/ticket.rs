@@ -6,11 +6,13 @@ use std::slice::Iter;
 #[derive(Clone)]
 pub struct Ticket {
     pub values: Vec<u32>,
+    /// Line number the ticket was read from, starting at 1.
+    pub line: usize,
 }
 
 impl Ticket {
-    pub fn new(values: Vec<u32>) -> Self {
-        Self { values: values }
+    pub fn new(values: Vec<u32>, line: usize) -> Self {
+        Self { values, line }
     }
 
     /// Returns an iterator over all field values in a ticket.
@@ -2,23 +2,19 @@
 
 use std::cmp::{Eq, PartialEq};
 use std::hash::{Hash, Hasher};
-use std::ops::Range;
+
+use crate::IntervalSet;
 
 /// Defines rules for a ticket field, used to determine validity of a field.
 #[derive(Clone)]
 pub struct TicketFieldRule {
     name: String,
-    r1: Range<u32>,
-    r2: Range<u32>,
+    ranges: IntervalSet,
 }
 
 impl TicketFieldRule {
-    pub fn new(name: String, range1: Range<u32>, range2: Range<u32>) -> Self {
-        Self {
-            name: name,
-            r1: range1,
-            r2: range2,
-        }
+    pub fn new(name: String, ranges: IntervalSet) -> Self {
+        Self { name, ranges }
     }
 
     /// Gets the name of a field.
@@ -26,15 +22,20 @@ impl TicketFieldRule {
         self.name.clone()
     }
 
+    /// Gets the values that are valid for this rule.
+    pub fn get_ranges(&self) -> &IntervalSet {
+        &self.ranges
+    }
+
     /// Checks whether a value is valid for this rule.
     pub fn check_value(&self, v: u32) -> bool {
-        self.r1.contains(&v) || self.r2.contains(&v)
+        self.ranges.contains(v)
     }
 }
 
 impl PartialEq for TicketFieldRule {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name && self.r1 == other.r1 && self.r2 == other.r2
+        self.name == other.name && self.ranges == other.ranges
     }
 }
 
@@ -43,7 +44,6 @@ impl Eq for TicketFieldRule {}
 impl Hash for TicketFieldRule {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.name.hash(state);
-        self.r1.hash(state);
-        self.r2.hash(state);
+        self.ranges.hash(state);
     }
 }
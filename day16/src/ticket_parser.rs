@@ -1,26 +1,62 @@
 //! Provides a parser for reading ticket field rules and tickets from a file.
 
-use std::ops::Range;
 use std::path::Path;
 use utils::read_lines;
 
-use crate::{Ticket, TicketFieldRule};
+use crate::{IntervalSet, Ticket, TicketFieldRule};
 
-/// Parses a string into a range.
-fn parse_range(s: &str) -> Result<Range<u32>, ()> {
-    let tokens: Vec<&str> = s.split('-').collect();
-    if tokens.len() != 2 {
-        return Err(());
-    }
-    let low: u32 = match tokens[0].parse() {
-        Ok(n) => n,
-        Err(_) => return Err(()),
+/// Parses a range into its inclusive bounds. A range is either a single value `a`, a
+/// closed range `a-b`, or an open range `a-` or `-b`.
+fn parse_range(s: &str) -> Result<(u32, u32), ()> {
+    let parse_bound = |t: &str, default: u32| -> Result<u32, ()> {
+        if t.is_empty() {
+            Ok(default)
+        } else {
+            t.parse().map_err(|_| ())
+        }
     };
-    let high: u32 = match tokens[1].parse() {
-        Ok(n) => n,
-        Err(_) => return Err(()),
+    let (low, high) = match s.split_once('-') {
+        Some((low_str, high_str)) => {
+            if low_str.is_empty() && high_str.is_empty() {
+                return Err(());
+            }
+            (parse_bound(low_str, 0)?, parse_bound(high_str, u32::MAX)?)
+        }
+        None => {
+            let v = s.parse().map_err(|_| ())?;
+            (v, v)
+        }
     };
-    Ok(low..high + 1)
+    if low > high {
+        return Err(());
+    }
+    Ok((low, high))
+}
+
+/// Parses the ranges of a rule, separated by `or`. Ranges prefixed with `not` are
+/// excluded from the others, or from every value if there are no others.
+fn parse_rule_ranges(s: &str) -> Result<IntervalSet, String> {
+    let mut included = IntervalSet::new();
+    let mut excluded = Vec::new();
+    for term in s.split(" or ").map(|t| t.trim()) {
+        let (negated, range_str) = match term.strip_prefix("not ") {
+            Some(r) => (true, r.trim()),
+            None => (false, term),
+        };
+        let (low, high) = parse_range(range_str).map_err(|_| format!("Bad range \"{}\"", term))?;
+        if negated {
+            excluded.push((low, high));
+        } else {
+            included.insert(low, high);
+        }
+    }
+    if included.intervals().is_empty() {
+        included = IntervalSet::full();
+    }
+    for (low, high) in excluded {
+        included.remove(low, high);
+    }
+    Ok(included)
 }
 
 /// Reads tickets rules, your ticket, and other tickets from a file.
@@ -63,25 +99,13 @@ where
             }
         } else if reading_rules {
             // Read a rule
-            let tokens: Vec<&str> = line.split(':').collect();
-            if tokens.len() != 2 {
-                return Err(format!("Invalid rule on line {}", i + 1));
-            }
-            let name = String::from(&tokens[0][0..tokens[0].len() - 1]);
-
-            let range_tokens: Vec<&str> = tokens[1].split(' ').collect();
-            if range_tokens.len() != 4 {
-                return Err(format!("Invalid ranges on line {}", i + 1));
-            }
-            let r1 = match parse_range(range_tokens[1]) {
-                Ok(r) => r,
-                Err(_) => return Err(format!("Bad range on line {}", i + 1)),
-            };
-            let r2 = match parse_range(range_tokens[3]) {
-                Ok(r) => r,
-                Err(_) => return Err(format!("Bad range on line {}", i + 1)),
+            let (name, ranges_str) = match line.split_once(':') {
+                Some(v) => v,
+                None => return Err(format!("Invalid rule on line {}", i + 1)),
             };
-            ticket_rules.push(TicketFieldRule::new(name.into(), r1, r2));
+            let ranges =
+                parse_rule_ranges(ranges_str).map_err(|e| format!("{} on line {}", e, i + 1))?;
+            ticket_rules.push(TicketFieldRule::new(name.trim().into(), ranges));
         } else {
             // Read a ticket
             let mut values: Vec<u32> = Vec::new();
@@ -91,7 +115,7 @@ where
                     Err(_) => return Err(format!("Failed to parse value on line {}", i + 1)),
                 }
             }
-            let ticket = Ticket::new(values);
+            let ticket = Ticket::new(values, i + 1);
 
             // Store ticket
             if reading_your_ticket {
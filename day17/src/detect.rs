@@ -0,0 +1,214 @@
+//! Provides detection of repeating states in a simulation.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::sparse::Coord;
+
+/// The long-term behaviour of a simulation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// Every cube died out by `cycle`.
+    DiedOut { cycle: usize },
+    /// The cubes stopped changing at `cycle`.
+    Static { cycle: usize },
+    /// The cubes repeat in place every `period` cycles starting at `cycle`.
+    Oscillating { cycle: usize, period: usize },
+    /// The cubes repeat every `period` cycles starting at `cycle`, moved by `offset`
+    /// each period.
+    Gliding {
+        cycle: usize,
+        period: usize,
+        offset: Coord,
+    },
+}
+
+impl Outcome {
+    /// Returns the cycle the repeating behaviour starts at.
+    pub fn start_cycle(&self) -> usize {
+        match *self {
+            Self::DiedOut { cycle }
+            | Self::Static { cycle }
+            | Self::Oscillating { cycle, .. }
+            | Self::Gliding { cycle, .. } => cycle,
+        }
+    }
+
+    /// Returns how many cycles it takes for the behaviour to repeat.
+    pub fn period(&self) -> usize {
+        match *self {
+            Self::DiedOut { .. } | Self::Static { .. } => 1,
+            Self::Oscillating { period, .. } | Self::Gliding { period, .. } => period,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DiedOut { cycle } => write!(f, "died out at cycle {}", cycle),
+            Self::Static { cycle } => write!(f, "became static at cycle {}", cycle),
+            Self::Oscillating { cycle, period } => write!(
+                f,
+                "oscillates with period {} starting at cycle {}",
+                period, cycle
+            ),
+            Self::Gliding {
+                cycle,
+                period,
+                offset,
+            } => write!(
+                f,
+                "glides by {:?} every {} cycles starting at cycle {}",
+                offset, period, cycle
+            ),
+        }
+    }
+}
+
+/// Detects when a simulation's cubes die out, stop changing, oscillate, or repeat
+/// translated elsewhere.
+///
+/// States are compared by the cubes' positions relative to the smallest corner of
+/// their bounding box, so translated copies of a state are equal. States are looked up
+/// by hash, and the positions are compared in full to rule out hash collisions.
+pub struct CycleDetector {
+    /// Maps state hashes to each state with that hash, along with the cycle it was
+    /// first seen and its bounding box corner at that cycle.
+    seen: HashMap<u64, Vec<SeenState>>,
+    /// Cycle numbers and cube counts for every observed cycle, in order.
+    counts: Vec<(usize, usize)>,
+    outcome: Option<Outcome>,
+}
+
+/// A state recorded by `CycleDetector`.
+struct SeenState {
+    /// Cycle the state was first seen at.
+    cycle: usize,
+    /// Smallest corner of the cubes' bounding box at that cycle.
+    corner: Coord,
+    /// Sorted positions of the cubes relative to `corner`.
+    relative: Vec<Coord>,
+}
+
+/// Moves a set of cubes so the smallest corner of their bounding box is at the origin.
+///
+/// # Returns
+///
+/// The sorted positions relative to the corner, their hash, and the corner.
+fn normalize<'a, I>(cubes: I) -> (Vec<Coord>, u64, Coord)
+where
+    I: IntoIterator<Item = &'a Coord>,
+{
+    let cubes: Vec<Coord> = cubes.into_iter().cloned().collect();
+    let mut min = match cubes.first() {
+        Some(c) => *c,
+        None => (0, 0, 0),
+    };
+    for (x, y, z) in cubes.iter() {
+        min = (min.0.min(*x), min.1.min(*y), min.2.min(*z));
+    }
+    let mut relative: Vec<Coord> = cubes
+        .iter()
+        .map(|(x, y, z)| (x - min.0, y - min.1, z - min.2))
+        .collect();
+    relative.sort_unstable();
+    let mut hasher = DefaultHasher::new();
+    relative.hash(&mut hasher);
+    (relative, hasher.finish(), min)
+}
+
+impl CycleDetector {
+    /// Creates a detector that has not observed any cycles.
+    pub fn new() -> Self {
+        Self {
+            seen: HashMap::new(),
+            counts: Vec::new(),
+            outcome: None,
+        }
+    }
+
+    /// Records the state of the cubes at a cycle. Cycles must be observed one after
+    /// another.
+    ///
+    /// # Returns
+    ///
+    /// The simulation's outcome once it has been determined.
+    pub fn observe<'a, I>(&mut self, cycle: usize, cubes: I) -> Option<Outcome>
+    where
+        I: IntoIterator<Item = &'a Coord>,
+    {
+        if self.outcome.is_some() {
+            return self.outcome;
+        }
+        let cubes: Vec<&Coord> = cubes.into_iter().collect();
+        self.counts.push((cycle, cubes.len()));
+        if cubes.is_empty() {
+            self.outcome = Some(Outcome::DiedOut { cycle });
+            return self.outcome;
+        }
+
+        let (relative, hash, corner) = normalize(cubes);
+        let states = self.seen.entry(hash).or_default();
+        let (prev_cycle, prev_corner) = match states.iter().find(|s| s.relative == relative) {
+            Some(seen) => (seen.cycle, seen.corner),
+            None => {
+                states.push(SeenState {
+                    cycle,
+                    corner,
+                    relative,
+                });
+                return None;
+            }
+        };
+        let period = cycle - prev_cycle;
+        let offset = (
+            corner.0 - prev_corner.0,
+            corner.1 - prev_corner.1,
+            corner.2 - prev_corner.2,
+        );
+        self.outcome = Some(if offset != (0, 0, 0) {
+            Outcome::Gliding {
+                cycle: prev_cycle,
+                period,
+                offset,
+            }
+        } else if period == 1 {
+            Outcome::Static { cycle: prev_cycle }
+        } else {
+            Outcome::Oscillating {
+                cycle: prev_cycle,
+                period,
+            }
+        });
+        self.outcome
+    }
+
+    /// Returns the simulation's outcome, if it has been determined.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// Returns how many cubes there are at a cycle. Cycles past the last observed
+    /// cycle are extrapolated once the outcome is known.
+    pub fn count_at(&self, cycle: usize) -> Option<usize> {
+        let (first_cycle, _) = *self.counts.first()?;
+        let (last_cycle, _) = *self.counts.last()?;
+        if cycle < first_cycle {
+            return None;
+        }
+        let cycle = if cycle <= last_cycle {
+            cycle
+        } else {
+            let outcome = self.outcome?;
+            if let Outcome::DiedOut { .. } = outcome {
+                return Some(0);
+            }
+            let start = outcome.start_cycle();
+            start + (cycle - start) % outcome.period()
+        };
+        Some(self.counts[cycle - first_cycle].1)
+    }
+}
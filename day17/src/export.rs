@@ -0,0 +1,205 @@
+//! Provides exporters for writing grids to voxel and mesh files.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::grid::Expand3DGrid;
+use crate::sparse::Coord;
+
+/// Largest dimension supported by the MagicaVoxel format.
+const VOX_MAX_DIM: usize = 256;
+
+/// Palette index used for every voxel in `.vox` exports.
+const VOX_COLOR_INDEX: u8 = 1;
+
+/// A face of a unit cube, as the neighbour offset the face points towards and its
+/// four corners in counter-clockwise order when viewed from outside.
+type CubeFace = (Coord, [Coord; 4]);
+
+/// Faces of a unit cube.
+const CUBE_FACES: [CubeFace; 6] = [
+    ((-1, 0, 0), [(0, 0, 0), (0, 0, 1), (0, 1, 1), (0, 1, 0)]),
+    ((1, 0, 0), [(1, 0, 0), (1, 1, 0), (1, 1, 1), (1, 0, 1)]),
+    ((0, -1, 0), [(0, 0, 0), (1, 0, 0), (1, 0, 1), (0, 0, 1)]),
+    ((0, 1, 0), [(0, 1, 0), (0, 1, 1), (1, 1, 1), (1, 1, 0)]),
+    ((0, 0, -1), [(0, 0, 0), (0, 1, 0), (1, 1, 0), (1, 0, 0)]),
+    ((0, 0, 1), [(0, 0, 1), (1, 0, 1), (1, 1, 1), (0, 1, 1)]),
+];
+
+/// File formats a grid can be exported to.
+#[derive(Clone, Copy)]
+pub enum ExportFormat {
+    /// MagicaVoxel `.vox`.
+    Vox,
+    /// ASCII Stanford `.ply` mesh.
+    Ply,
+    /// Wavefront `.obj` mesh.
+    Obj,
+}
+
+impl ExportFormat {
+    /// Parses a format from its name or file extension.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "vox" => Some(Self::Vox),
+            "ply" => Some(Self::Ply),
+            "obj" => Some(Self::Obj),
+            _ => None,
+        }
+    }
+
+    /// Returns the file extension for the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Vox => "vox",
+            Self::Ply => "ply",
+            Self::Obj => "obj",
+        }
+    }
+}
+
+/// A surface mesh of quads.
+struct Mesh {
+    vertices: Vec<Coord>,
+    faces: Vec<[usize; 4]>,
+}
+
+/// Builds a mesh of the outer surface of every cube in a grid. Faces shared by two
+/// cubes are omitted.
+fn build_mesh(grid: &Expand3DGrid) -> Mesh {
+    let cubes: HashSet<Coord> = grid.active_cubes().into_iter().collect();
+    let mut sorted: Vec<&Coord> = cubes.iter().collect();
+    sorted.sort();
+
+    let mut vertex_ids: HashMap<Coord, usize> = HashMap::new();
+    let mut mesh = Mesh {
+        vertices: Vec::new(),
+        faces: Vec::new(),
+    };
+    for (x, y, z) in sorted.into_iter() {
+        for ((dx, dy, dz), corners) in CUBE_FACES.iter() {
+            if cubes.contains(&(x + dx, y + dy, z + dz)) {
+                continue;
+            }
+            let mut face = [0; 4];
+            for (i, (cx, cy, cz)) in corners.iter().enumerate() {
+                let vertex = (x + cx, y + cy, z + cz);
+                face[i] = *vertex_ids.entry(vertex).or_insert_with(|| {
+                    mesh.vertices.push(vertex);
+                    mesh.vertices.len() - 1
+                });
+            }
+            mesh.faces.push(face);
+        }
+    }
+    mesh
+}
+
+/// Writes a RIFF-style `.vox` chunk header.
+fn write_vox_chunk_header<W: Write>(
+    out: &mut W,
+    id: &[u8; 4],
+    content_size: u32,
+    children_size: u32,
+) -> io::Result<()> {
+    out.write_all(id)?;
+    out.write_all(&content_size.to_le_bytes())?;
+    out.write_all(&children_size.to_le_bytes())
+}
+
+/// Writes a grid in the MagicaVoxel `.vox` format.
+fn write_vox<W: Write>(out: &mut W, grid: &Expand3DGrid) -> io::Result<()> {
+    let (w, h, d) = grid.get_size();
+    if w > VOX_MAX_DIM || h > VOX_MAX_DIM || d > VOX_MAX_DIM {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Grid of size {}x{}x{} exceeds the .vox limit of {} per axis",
+                w, h, d, VOX_MAX_DIM
+            ),
+        ));
+    }
+    let (ox, oy, oz) = grid.get_offset();
+    let cubes = grid.active_cubes();
+
+    let size_content = 12;
+    let xyzi_content = 4 + 4 * cubes.len() as u32;
+    let children = (12 + size_content) + (12 + xyzi_content);
+
+    out.write_all(b"VOX ")?;
+    out.write_all(&150u32.to_le_bytes())?;
+    write_vox_chunk_header(out, b"MAIN", 0, children)?;
+
+    write_vox_chunk_header(out, b"SIZE", size_content, 0)?;
+    for dim in [w, h, d].iter() {
+        out.write_all(&(*dim as u32).to_le_bytes())?;
+    }
+
+    write_vox_chunk_header(out, b"XYZI", xyzi_content, 0)?;
+    out.write_all(&(cubes.len() as u32).to_le_bytes())?;
+    for (x, y, z) in cubes.iter() {
+        out.write_all(&[
+            (x - ox) as u8,
+            (y - oy) as u8,
+            (z - oz) as u8,
+            VOX_COLOR_INDEX,
+        ])?;
+    }
+    Ok(())
+}
+
+/// Writes the surface of a grid as an ASCII `.ply` mesh.
+fn write_ply<W: Write>(out: &mut W, grid: &Expand3DGrid) -> io::Result<()> {
+    let mesh = build_mesh(grid);
+    writeln!(out, "ply")?;
+    writeln!(out, "format ascii 1.0")?;
+    writeln!(out, "element vertex {}", mesh.vertices.len())?;
+    writeln!(out, "property int x")?;
+    writeln!(out, "property int y")?;
+    writeln!(out, "property int z")?;
+    writeln!(out, "element face {}", mesh.faces.len())?;
+    writeln!(out, "property list uchar int vertex_indices")?;
+    writeln!(out, "end_header")?;
+    for (x, y, z) in mesh.vertices.iter() {
+        writeln!(out, "{} {} {}", x, y, z)?;
+    }
+    for [a, b, c, d] in mesh.faces.iter() {
+        writeln!(out, "4 {} {} {} {}", a, b, c, d)?;
+    }
+    Ok(())
+}
+
+/// Writes the surface of a grid as a Wavefront `.obj` mesh.
+fn write_obj<W: Write>(out: &mut W, grid: &Expand3DGrid) -> io::Result<()> {
+    let mesh = build_mesh(grid);
+    for (x, y, z) in mesh.vertices.iter() {
+        writeln!(out, "v {} {} {}", x, y, z)?;
+    }
+    // OBJ vertex indices are 1-based.
+    for [a, b, c, d] in mesh.faces.iter() {
+        writeln!(out, "f {} {} {} {}", a + 1, b + 1, c + 1, d + 1)?;
+    }
+    Ok(())
+}
+
+/// Exports a grid to a file.
+///
+/// # Arguments
+///
+///  * `path` - Path of the file to write.
+///  * `grid` - Grid to export.
+///  * `format` - Format to write the grid in.
+pub fn export_grid<P>(path: P, grid: &Expand3DGrid, format: ExportFormat) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let mut out = BufWriter::new(File::create(path)?);
+    match format {
+        ExportFormat::Vox => write_vox(&mut out, grid)?,
+        ExportFormat::Ply => write_ply(&mut out, grid)?,
+        ExportFormat::Obj => write_obj(&mut out, grid)?,
+    }
+    out.flush()
+}
@@ -7,6 +7,8 @@ pub struct Expand3DGrid {
     grid: Vec<Vec<Vec<bool>>>,
     /// Width, height, and depth.
     size: (usize, usize, usize),
+    /// World coordinates of the voxel at index (0, 0, 0).
+    offset: (i64, i64, i64),
 }
 
 enum Axis {
@@ -64,24 +66,53 @@ impl Expand3DGrid {
     /// Create a new grid with a starting width, height, and depth of `size`.
     pub fn new(size: (usize, usize, usize)) -> Self {
         let (w, h, d) = size;
-        let mut grid = Vec::new();
-        for _ in 0..d {
-            let mut plane = Vec::new();
-            for _ in 0..h {
-                let mut row = Vec::new();
-                for _ in 0..w {
-                    row.push(false);
-                }
-                plane.push(row);
-            }
-            grid.push(plane);
-        }
+        let grid = vec![vec![vec![false; w]; h]; d];
         Self {
-            grid: grid,
-            size: size,
+            grid,
+            size,
+            offset: (0, 0, 0),
         }
     }
 
+    /// Create a new grid of size `size` whose voxel at index (0, 0, 0) lies at world
+    /// coordinates `offset`.
+    pub fn with_offset(size: (usize, usize, usize), offset: (i64, i64, i64)) -> Self {
+        let mut grid = Self::new(size);
+        grid.offset = offset;
+        grid
+    }
+
+    /// Creates the smallest grid containing every cube in `cubes`, given in world
+    /// coordinates. An empty set of cubes yields a 1x1x1 grid at the origin.
+    pub fn from_cubes<'a, I>(cubes: I) -> Self
+    where
+        I: IntoIterator<Item = &'a (i64, i64, i64)>,
+    {
+        let cubes: Vec<(i64, i64, i64)> = cubes.into_iter().cloned().collect();
+        if cubes.is_empty() {
+            return Self::new((1, 1, 1));
+        }
+        let (mut min, mut max) = (cubes[0], cubes[0]);
+        for (x, y, z) in cubes.iter() {
+            min = (min.0.min(*x), min.1.min(*y), min.2.min(*z));
+            max = (max.0.max(*x), max.1.max(*y), max.2.max(*z));
+        }
+        let size = (
+            (max.0 - min.0 + 1) as usize,
+            (max.1 - min.1 + 1) as usize,
+            (max.2 - min.2 + 1) as usize,
+        );
+        let mut grid = Self::with_offset(size, min);
+        for (x, y, z) in cubes.iter() {
+            grid.toggle((
+                (x - min.0) as usize,
+                (y - min.1) as usize,
+                (z - min.2) as usize,
+            ));
+        }
+        grid
+    }
+
     /// Toggles whether a position has a cube or not.
     pub fn toggle(&mut self, xyz: (usize, usize, usize)) {
         let (w, h, d) = self.size;
@@ -133,10 +164,31 @@ impl Expand3DGrid {
     }
 
     /// Returns the (width, depth, height) of the grid.
-    pub fn get_size(&mut self) -> (usize, usize, usize) {
+    pub fn get_size(&self) -> (usize, usize, usize) {
         self.size
     }
 
+    /// Returns the world coordinates of the voxel at index (0, 0, 0).
+    pub fn get_offset(&self) -> (i64, i64, i64) {
+        self.offset
+    }
+
+    /// Returns the world coordinates of every cube in the grid.
+    pub fn active_cubes(&self) -> Vec<(i64, i64, i64)> {
+        let (ox, oy, oz) = self.offset;
+        let mut cubes = Vec::new();
+        for (z, plane) in self.grid.iter().enumerate() {
+            for (y, row) in plane.iter().enumerate() {
+                for (x, has_cube) in row.iter().enumerate() {
+                    if *has_cube {
+                        cubes.push((ox + x as i64, oy + y as i64, oz + z as i64));
+                    }
+                }
+            }
+        }
+        cubes
+    }
+
     /// Expands the edges outward such that no voxels on any edge contains a cube.
     pub fn expand_border(&mut self) {
         // Find which edges, if any, need expansion.
@@ -163,50 +215,29 @@ impl Expand3DGrid {
                     row.insert(0, false);
                 }
             }
+            self.offset.0 -= 1;
             w += 1
         }
         if y_far {
             for plane in self.grid.iter_mut() {
-                let mut new_row = Vec::new();
-                for _ in 0..w {
-                    new_row.push(false);
-                }
-                plane.push(new_row);
+                plane.push(vec![false; w]);
             }
             h += 1;
         }
         if y_near {
             for plane in self.grid.iter_mut() {
-                let mut new_row = Vec::new();
-                for _ in 0..w {
-                    new_row.push(false);
-                }
-                plane.insert(0, new_row);
+                plane.insert(0, vec![false; w]);
             }
+            self.offset.1 -= 1;
             h += 1;
         }
         if z_far {
-            let mut new_plane = Vec::new();
-            for _ in 0..h {
-                let mut row = Vec::new();
-                for _ in 0..w {
-                    row.push(false);
-                }
-                new_plane.push(row);
-            }
-            self.grid.push(new_plane);
+            self.grid.push(vec![vec![false; w]; h]);
             d += 1
         }
         if z_near {
-            let mut new_plane = Vec::new();
-            for _ in 0..h {
-                let mut row = Vec::new();
-                for _ in 0..w {
-                    row.push(false);
-                }
-                new_plane.push(row);
-            }
-            self.grid.insert(0, new_plane);
+            self.grid.insert(0, vec![vec![false; w]; h]);
+            self.offset.2 -= 1;
             d += 1
         }
         self.size = (w, h, d);
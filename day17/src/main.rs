@@ -1,18 +1,112 @@
 #[macro_use]
 extern crate utils;
 
+mod detect;
+mod export;
 mod grid;
+mod snapshot;
+mod sparse;
 
 use std::env;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::thread;
 use utils::read_lines;
 
+use detect::{CycleDetector, Outcome};
+use export::{export_grid, ExportFormat};
 use grid::Expand3DGrid;
+use snapshot::{load_snapshot, save_snapshot};
+use sparse::SparseGrid;
+
+/// Command line options.
+struct Options {
+    /// Puzzle input to start from. Unused when resuming from a snapshot.
+    input_path: Option<String>,
+    /// Snapshot to resume the simulation from, if any.
+    resume_path: Option<PathBuf>,
+    /// Path to save a snapshot to once the simulation finishes, if any.
+    save_path: Option<PathBuf>,
+    /// Number of cycles to simulate, in addition to any already simulated.
+    cycles: usize,
+    /// Number of threads to use for sparse simulation.
+    threads: usize,
+    /// Whether to use the dense grid simulation instead of the sparse one.
+    dense: bool,
+    /// Directory to export the grid to after every cycle, if any.
+    export_dir: Option<PathBuf>,
+    /// Format to export grids in.
+    export_format: ExportFormat,
+    /// Whether to stop simulating once the cubes die out or start repeating.
+    detect: bool,
+}
 
 /// Prints usage statement for the executable.
 fn usage(args: Vec<String>) {
-    println!("Usage: {} input_file", args[0]);
+    println!(
+        "Usage: {} (input_file | --resume SNAPSHOT) [--save SNAPSHOT] [--cycles N] [--threads N] [--dense] [--detect] [--export DIR] [--format vox|ply|obj]",
+        args[0]
+    );
+}
+
+/// Parses a numeric value for a command line flag.
+fn parse_flag_value(flag: &str, value: Option<&String>) -> Result<usize, String> {
+    match value {
+        Some(v) => v
+            .parse()
+            .map_err(|_| format!("Invalid value for {}: {}", flag, v)),
+        None => Err(format!("Missing value for {}", flag)),
+    }
+}
+
+/// Parses command line arguments.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options {
+        input_path: None,
+        resume_path: None,
+        save_path: None,
+        cycles: 6,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        dense: false,
+        export_dir: None,
+        export_format: ExportFormat::Vox,
+        detect: false,
+    };
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--cycles" => opts.cycles = parse_flag_value(arg, iter.next())?,
+            "--threads" => opts.threads = parse_flag_value(arg, iter.next())?,
+            "--dense" => opts.dense = true,
+            "--detect" => opts.detect = true,
+            "--export" => match iter.next() {
+                Some(dir) => opts.export_dir = Some(PathBuf::from(dir)),
+                None => return Err("Missing value for --export".into()),
+            },
+            "--resume" => match iter.next() {
+                Some(path) => opts.resume_path = Some(PathBuf::from(path)),
+                None => return Err("Missing value for --resume".into()),
+            },
+            "--save" => match iter.next() {
+                Some(path) => opts.save_path = Some(PathBuf::from(path)),
+                None => return Err("Missing value for --save".into()),
+            },
+            "--format" => match iter.next().and_then(|f| ExportFormat::from_name(f)) {
+                Some(format) => opts.export_format = format,
+                None => return Err("Expected one of vox, ply, or obj for --format".into()),
+            },
+            _ if opts.input_path.is_none() && !arg.starts_with("--") => {
+                opts.input_path = Some(arg.clone())
+            }
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+    match (&opts.input_path, &opts.resume_path) {
+        (None, None) => Err("Missing input file".into()),
+        (Some(_), Some(_)) => Err("Cannot give both an input file and --resume".into()),
+        _ => Ok(opts),
+    }
 }
 
 /// Reads a grid from a file.
@@ -67,7 +161,7 @@ fn simulate_cycle(grid: &mut Expand3DGrid) {
                 let xyz = (x, y, z);
                 let adj = grid.count_adj(xyz);
                 if grid.has_cube(xyz) {
-                    if adj < 2 || adj > 3 {
+                    if !(2..=3).contains(&adj) {
                         to_toggle.push(xyz);
                     }
                 } else if adj == 3 {
@@ -81,41 +175,182 @@ fn simulate_cycle(grid: &mut Expand3DGrid) {
     }
 }
 
-/// Simulates the a grid of cubes for `cycles` cycles.
-fn simulate_grid(grid: &mut Expand3DGrid, cycles: usize) {
-    for _ in 0..cycles {
+/// Simulates the a grid of cubes for `cycles` cycles. `on_cycle` is called with the
+/// cycle number and grid before the first cycle and after every cycle, and stops the
+/// simulation early by returning `Ok(false)`.
+///
+/// # Returns
+///
+/// The number of cycles simulated.
+fn simulate_grid<F>(
+    grid: &mut Expand3DGrid,
+    cycles: usize,
+    mut on_cycle: F,
+) -> Result<usize, String>
+where
+    F: FnMut(usize, &Expand3DGrid) -> Result<bool, String>,
+{
+    if !on_cycle(0, grid)? {
+        return Ok(0);
+    }
+    for cycle in 1..=cycles {
         simulate_cycle(grid);
+        if !on_cycle(cycle, grid)? {
+            return Ok(cycle);
+        }
+    }
+    Ok(cycles)
+}
+
+/// Simulates a grid of cubes for `cycles` cycles using the sparse representation,
+/// spreading each cycle across `threads` threads. `on_cycle` is called with the cycle
+/// number and grid before the first cycle and after every cycle, and stops the
+/// simulation early by returning `Ok(false)`.
+///
+/// # Returns
+///
+/// The simulated grid and the number of cycles simulated.
+fn simulate_sparse<F>(
+    grid: &Expand3DGrid,
+    cycles: usize,
+    threads: usize,
+    mut on_cycle: F,
+) -> Result<(SparseGrid, usize), String>
+where
+    F: FnMut(usize, &SparseGrid) -> Result<bool, String>,
+{
+    let mut sparse = SparseGrid::from_grid(grid);
+    if !on_cycle(0, &sparse)? {
+        return Ok((sparse, 0));
+    }
+    for cycle in 1..=cycles {
+        sparse.step(threads);
+        if !on_cycle(cycle, &sparse)? {
+            return Ok((sparse, cycle));
+        }
+    }
+    Ok((sparse, cycles))
+}
+
+/// Exports a grid for a given cycle into `dir`, if a directory is given.
+fn export_cycle(
+    dir: &Option<PathBuf>,
+    format: ExportFormat,
+    cycle: usize,
+    grid: &Expand3DGrid,
+) -> Result<(), String> {
+    let dir = match dir {
+        Some(d) => d,
+        None => return Ok(()),
+    };
+    let path = dir.join(format!("cycle_{:04}.{}", cycle, format.extension()));
+    export_grid(&path, grid, format)
+        .map_err(|e| format!("Failed to export {}: {}", path.display(), e))
+}
+
+/// Saves a snapshot of a grid to `path`, if a path is given.
+fn save_cycle(path: &Option<PathBuf>, grid: &Expand3DGrid, cycle: usize) -> Result<(), String> {
+    match path {
+        Some(p) => save_snapshot(p, grid, cycle)
+            .map_err(|e| format!("Failed to save snapshot {}: {}", p.display(), e)),
+        None => Ok(()),
     }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        usage(args);
-        process::exit(1);
-    }
-    let input_path = String::from(&args[1]);
-    let mut grid = timed_section!("Initialization", { read_grid(&input_path) }, |res| {
-        match res {
-            Ok(grid) => {
-                println!("Initial grid:\n{}", grid);
-                grid
+    let opts = match parse_args(&args) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}", e);
+            usage(args);
+            process::exit(1);
+        }
+    };
+    let (mut grid, start_cycle) = timed_section!(
+        "Initialization",
+        {
+            match (&opts.resume_path, &opts.input_path) {
+                (Some(path), _) => load_snapshot(path),
+                (None, Some(path)) => read_grid(path).map(|grid| (grid, 0)),
+                (None, None) => unreachable!(),
+            }
+        },
+        |res| match res {
+            Ok((grid, cycle)) => {
+                println!("Initial grid (cycle {}):\n{}", cycle, grid);
+                (grid, cycle)
             }
             Err(e) => {
                 eprintln!("Failed to read grid: {}", e);
                 process::exit(1);
             }
         }
-    });
+    );
+    let end_cycle = start_cycle + opts.cycles;
+
+    if let Some(dir) = &opts.export_dir {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("Failed to create export directory: {}", e);
+            process::exit(1);
+        }
+    }
 
     timed_section!(
         "Part 1",
         {
-            simulate_grid(&mut grid, 6);
-            grid.count_cubes()
+            let (dir, format) = (&opts.export_dir, opts.export_format);
+            let mut detector = CycleDetector::new();
+            let res = if opts.dense {
+                simulate_grid(&mut grid, opts.cycles, |cycle, g| {
+                    export_cycle(dir, format, start_cycle + cycle, g)?;
+                    Ok(!opts.detect
+                        || detector
+                            .observe(start_cycle + cycle, g.active_cubes().iter())
+                            .is_none())
+                })
+                .and_then(|last| {
+                    save_cycle(&opts.save_path, &grid, start_cycle + last)?;
+                    Ok(grid.count_cubes())
+                })
+            } else {
+                simulate_sparse(&grid, opts.cycles, opts.threads, |cycle, sparse| {
+                    if dir.is_some() {
+                        export_cycle(dir, format, start_cycle + cycle, &sparse.to_grid())?;
+                    }
+                    Ok(!opts.detect
+                        || detector
+                            .observe(start_cycle + cycle, sparse.get_active())
+                            .is_none())
+                })
+                .and_then(|(sparse, last)| {
+                    if opts.save_path.is_some() {
+                        save_cycle(&opts.save_path, &sparse.to_grid(), start_cycle + last)?;
+                    }
+                    Ok(sparse.count_cubes())
+                })
+            };
+            // Fill in the count for any cycles skipped once the outcome was known.
+            res.map(|cnt| {
+                (
+                    detector.count_at(end_cycle).unwrap_or(cnt),
+                    detector.outcome(),
+                )
+            })
         },
-        |cnt| {
-            println!("Cubes left after 6 cycles: {}", cnt);
+        |res: Result<(usize, Option<Outcome>), String>| match res {
+            Ok((cnt, outcome)) => {
+                if let Some(outcome) = outcome {
+                    println!("Simulation {}", outcome);
+                } else if opts.detect {
+                    println!("No repeating state found");
+                }
+                println!("Cubes left after {} cycles: {}", end_cycle, cnt);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
         }
     );
 }
@@ -0,0 +1,166 @@
+//! Provides saving and loading of grid snapshots.
+//!
+//! Snapshots are plain text so they can be inspected by hand:
+//!
+//! ```text
+//! cycle 6
+//! size 7 7 5
+//! offset -2 -2 -2
+//! cubes
+//! 0 -1 -2
+//! ...
+//! ```
+//!
+//! Cube positions are given in world coordinates.
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use utils::read_lines;
+
+use crate::grid::Expand3DGrid;
+
+/// Most voxels a loaded snapshot's grid may have. Larger sizes are rejected rather than
+/// allocated, since the size is read from the file.
+const MAX_SNAPSHOT_VOXELS: usize = 1 << 27;
+/// Largest absolute offset a loaded snapshot's grid may have, which leaves room for the
+/// grid to keep expanding without overflowing.
+const MAX_SNAPSHOT_OFFSET: i64 = 1 << 40;
+
+/// Saves a grid and the number of cycles it has been simulated for.
+///
+/// # Arguments
+///
+///  * `path` - Path of the snapshot file to write.
+///  * `grid` - Grid to save.
+///  * `cycle` - Number of cycles simulated so far.
+pub fn save_snapshot<P>(path: P, grid: &Expand3DGrid, cycle: usize) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let mut out = BufWriter::new(File::create(path)?);
+    let (w, h, d) = grid.get_size();
+    let (ox, oy, oz) = grid.get_offset();
+    writeln!(out, "cycle {}", cycle)?;
+    writeln!(out, "size {} {} {}", w, h, d)?;
+    writeln!(out, "offset {} {} {}", ox, oy, oz)?;
+    writeln!(out, "cubes")?;
+    for (x, y, z) in grid.active_cubes().iter() {
+        writeln!(out, "{} {} {}", x, y, z)?;
+    }
+    out.flush()
+}
+
+/// Parses a line of three whitespace-separated numbers.
+fn parse_triple<T: std::str::FromStr>(s: &str) -> Option<(T, T, T)> {
+    let nums: Vec<T> = s
+        .split_whitespace()
+        .map(|n| n.parse().ok())
+        .collect::<Option<Vec<T>>>()?;
+    if nums.len() != 3 {
+        return None;
+    }
+    let mut iter = nums.into_iter();
+    Some((iter.next()?, iter.next()?, iter.next()?))
+}
+
+/// Parses a `key value` header line.
+fn parse_header<'a>(line: Option<&'a String>, key: &str) -> Result<&'a str, String> {
+    match line.and_then(|l| l.strip_prefix(key)) {
+        Some(rest) if rest.starts_with(' ') => Ok(rest.trim()),
+        _ => Err(format!("Expected \"{}\" header", key)),
+    }
+}
+
+/// Loads a snapshot saved by `save_snapshot`.
+///
+/// # Arguments
+///
+///  * `path` - Path of the snapshot file.
+///
+/// # Returns
+///
+/// The saved grid and the number of cycles it had been simulated for.
+pub fn load_snapshot<P>(path: P) -> Result<(Expand3DGrid, usize), String>
+where
+    P: AsRef<Path>,
+{
+    if !path.as_ref().exists() {
+        return Err("File does not exists".into());
+    }
+    let unread_lines = match read_lines(path) {
+        Ok(l) => l,
+        Err(_) => return Err("Failed to read lines from file".into()),
+    };
+    let mut lines: Vec<String> = Vec::new();
+    for (i, line_res) in unread_lines.enumerate() {
+        match line_res {
+            Ok(l) => lines.push(l),
+            Err(_) => return Err(format!("Failed to read line {}", i + 1)),
+        }
+    }
+
+    let cycle = parse_header(lines.first(), "cycle")?
+        .parse()
+        .map_err(|_| "Invalid cycle count".to_string())?;
+    let size: (usize, usize, usize) =
+        parse_triple(parse_header(lines.get(1), "size")?).ok_or("Invalid size")?;
+    let offset: (i64, i64, i64) =
+        parse_triple(parse_header(lines.get(2), "offset")?).ok_or("Invalid offset")?;
+    if lines.get(3).map(|l| l.as_str()) != Some("cubes") {
+        return Err("Expected \"cubes\" header".into());
+    }
+    if size.0 == 0 || size.1 == 0 || size.2 == 0 {
+        return Err("Grid size must be non-zero".into());
+    }
+    match size
+        .0
+        .checked_mul(size.1)
+        .and_then(|n| n.checked_mul(size.2))
+    {
+        Some(voxels) if voxels <= MAX_SNAPSHOT_VOXELS => {}
+        _ => {
+            return Err(format!(
+                "Grid size is larger than {} voxels",
+                MAX_SNAPSHOT_VOXELS
+            ))
+        }
+    }
+
+    if [offset.0, offset.1, offset.2]
+        .iter()
+        .any(|o| o.unsigned_abs() > MAX_SNAPSHOT_OFFSET as u64)
+    {
+        return Err(format!(
+            "Grid offset is further than {} from the origin",
+            MAX_SNAPSHOT_OFFSET
+        ));
+    }
+
+    let mut grid = Expand3DGrid::with_offset(size, offset);
+    for (i, line) in lines.iter().enumerate().skip(4) {
+        if line.is_empty() {
+            continue;
+        }
+        let (x, y, z): (i64, i64, i64) =
+            parse_triple(line).ok_or(format!("Invalid cube on line {}", i + 1))?;
+        let index = |v: i64, offset: i64, len: usize| {
+            v.checked_sub(offset)
+                .and_then(|i| usize::try_from(i).ok())
+                .filter(|i| *i < len)
+        };
+        let xyz = match (
+            index(x, offset.0, size.0),
+            index(y, offset.1, size.1),
+            index(z, offset.2, size.2),
+        ) {
+            (Some(ix), Some(iy), Some(iz)) => (ix, iy, iz),
+            _ => return Err(format!("Cube on line {} lies outside the grid", i + 1)),
+        };
+        if !grid.has_cube(xyz) {
+            grid.toggle(xyz);
+        }
+    }
+    Ok((grid, cycle))
+}
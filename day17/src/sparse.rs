@@ -0,0 +1,92 @@
+//! Provides a sparse, hash-based representation of the pocket dimension.
+
+use std::collections::{HashMap, HashSet};
+use std::thread;
+
+use crate::grid::Expand3DGrid;
+
+/// A position in the pocket dimension.
+pub type Coord = (i64, i64, i64);
+
+/// A pocket dimension that only stores its active cubes.
+pub struct SparseGrid {
+    active: HashSet<Coord>,
+}
+
+/// Counts how many active cubes neighbour each position adjacent to `cubes`.
+fn count_neighbors(cubes: &[Coord]) -> HashMap<Coord, u8> {
+    let mut counts = HashMap::new();
+    for (x, y, z) in cubes.iter() {
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    if dx == 0 && dy == 0 && dz == 0 {
+                        continue;
+                    }
+                    *counts.entry((x + dx, y + dy, z + dz)).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    counts
+}
+
+impl SparseGrid {
+    /// Creates a sparse grid from the cubes in a dense grid.
+    pub fn from_grid(grid: &Expand3DGrid) -> Self {
+        Self {
+            active: grid.active_cubes().into_iter().collect(),
+        }
+    }
+
+    /// Creates a dense grid just large enough to hold every active cube.
+    pub fn to_grid(&self) -> Expand3DGrid {
+        Expand3DGrid::from_cubes(self.active.iter())
+    }
+
+    /// Returns the set of active cubes.
+    pub fn get_active(&self) -> &HashSet<Coord> {
+        &self.active
+    }
+
+    /// Counts how many cubes are active.
+    pub fn count_cubes(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Simulates a single cycle, splitting neighbour counting across up to `threads`
+    /// threads. Only positions adjacent to an active cube are visited.
+    pub fn step(&mut self, threads: usize) {
+        let cubes: Vec<Coord> = self.active.iter().cloned().collect();
+        let threads = threads.max(1).min(cubes.len().max(1));
+        let chunk_size = cubes.len().div_ceil(threads);
+
+        let counts = if threads == 1 {
+            count_neighbors(&cubes)
+        } else {
+            let partials: Vec<HashMap<Coord, u8>> = thread::scope(|s| {
+                let handles: Vec<_> = cubes
+                    .chunks(chunk_size)
+                    .map(|chunk| s.spawn(move || count_neighbors(chunk)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|h| h.join().expect("Neighbour counting thread panicked"))
+                    .collect()
+            });
+            let mut merged: HashMap<Coord, u8> = HashMap::new();
+            for partial in partials.into_iter() {
+                for (xyz, cnt) in partial.into_iter() {
+                    *merged.entry(xyz).or_insert(0) += cnt;
+                }
+            }
+            merged
+        };
+
+        self.active = counts
+            .into_iter()
+            .filter(|(xyz, cnt)| *cnt == 3 || (*cnt == 2 && self.active.contains(xyz)))
+            .map(|(xyz, _)| xyz)
+            .collect();
+    }
+}